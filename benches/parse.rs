@@ -0,0 +1,90 @@
+//! Compares parsing with serde_json and with simd-json
+//!
+//! Run with `cargo bench --features simd`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use jsonrpc_lite::{simd, JsonRpc};
+use serde_json::json;
+
+/// A request as a gateway typically receives it
+fn single() -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_call",
+        "params": [
+            {
+                "from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+                "to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+                "gas": "0x76c0",
+                "data": "0xd46e8dd67c5d32be8d46e8dd67c5d32be8058bb8eb970870f072445675058bb8eb970870f072445675"
+            },
+            "latest"
+        ],
+        "id": 1
+    })
+    .to_string()
+}
+
+/// A batch mixing requests, notifications and structured params
+fn batch() -> String {
+    let entries: Vec<_> = (0..32)
+        .map(|i| match i % 4 {
+            0 => json!({"jsonrpc": "2.0", "method": "sum", "params": [i, 2.5, -7], "id": i}),
+            1 => json!({"jsonrpc": "2.0", "method": "log", "params": {"level": "info", "text": "a \"quoted\" line\n"}}),
+            2 => json!({"jsonrpc": "2.0", "method": "get_data", "id": format!("req-{}", i)}),
+            _ => serde_json::from_str(&single()).unwrap(),
+        })
+        .collect();
+    serde_json::Value::Array(entries).to_string()
+}
+
+fn bench_single(c: &mut Criterion) {
+    let input = single();
+    let mut group = c.benchmark_group("single");
+    group.bench_function("serde_json", |b| {
+        b.iter(|| JsonRpc::parse(black_box(&input)).unwrap())
+    });
+    group.bench_function("simd", |b| {
+        b.iter_batched_ref(
+            || input.clone().into_bytes(),
+            |bytes| simd::parse(black_box(bytes)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let input = batch();
+    let mut group = c.benchmark_group("batch");
+    group.bench_function("serde_json", |b| {
+        b.iter(|| JsonRpc::parse_vec(black_box(&input)).unwrap())
+    });
+    group.bench_function("simd", |b| {
+        b.iter_batched_ref(
+            || input.clone().into_bytes(),
+            |bytes| simd::parse_vec(black_box(bytes)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_message(c: &mut Criterion) {
+    let input = batch();
+    let mut group = c.benchmark_group("message");
+    group.bench_function("serde_json", |b| {
+        b.iter(|| JsonRpc::parse_message(black_box(&input)).unwrap())
+    });
+    group.bench_function("simd", |b| {
+        b.iter_batched_ref(
+            || input.clone().into_bytes(),
+            |bytes| simd::parse_message(black_box(bytes)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_single, bench_batch, bench_message);
+criterion_main!(benches);
@@ -0,0 +1,170 @@
+//! Support shared by the binary encodings
+//!
+//! CBOR and MessagePack encode messages as maps with the same member names as
+//! their JSON form. Numbers are written as native integers or floats: with the
+//! `arbitrary_precision` feature, serde_json would otherwise hand them to the
+//! encoder as its private map representation.
+
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::ParseError;
+use crate::jsonrpc::{Message, ParseOptions};
+use crate::Error as RpcError;
+
+/// Serializes a value for a binary encoding, with every number as a native number
+///
+/// Without `arbitrary_precision` serde_json numbers already serialize natively
+/// and the value is passed through. With it, the value goes through a JSON
+/// value first, and integers beyond 64 bits are written as floats.
+pub(crate) struct Portable<'a, T: ?Sized>(pub(crate) &'a T);
+
+impl<T: Serialize + ?Sized> Serialize for Portable<'_, T> {
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = serde_json::to_value(self.0).map_err(serde::ser::Error::custom)?;
+        PortableValue(&value).serialize(serializer)
+    }
+}
+
+/// Serializes a JSON value with its numbers as native integers or floats
+#[cfg(feature = "arbitrary_precision")]
+struct PortableValue<'a>(&'a Value);
+
+#[cfg(feature = "arbitrary_precision")]
+impl Serialize for PortableValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self.0 {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(v),
+            Value::Number(ref n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+                (Some(v), _, _) => serializer.serialize_i64(v),
+                (_, Some(v), _) => serializer.serialize_u64(v),
+                (_, _, Some(v)) => serializer.serialize_f64(v),
+                _ => Err(serde::ser::Error::custom(format_args!(
+                    "number {} has no native representation",
+                    n
+                ))),
+            },
+            Value::String(ref v) => serializer.serialize_str(v),
+            Value::Array(ref values) => serializer.collect_seq(values.iter().map(PortableValue)),
+            Value::Object(ref map) => {
+                serializer.collect_map(map.iter().map(|(k, v)| (k, PortableValue(v))))
+            }
+        }
+    }
+}
+
+/// Builds a single message or a batch from a decoded value, mapping failures to spec errors
+pub(crate) fn parse_decoded<E: fmt::Display>(
+    decoded: Result<Value, E>,
+) -> Result<Message, ParseError> {
+    let value = decoded
+        .map_err(|err| ParseError::new(RpcError::parse_error().with_data(err.to_string()), None))?;
+    Message::from_json(value, &ParseOptions::default())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+
+    use crate::error::ParseError;
+    use crate::{Error as RpcError, Id, JsonRpc, Message, Params};
+
+    /// The entry points of a binary encoding, as exercised by the shared tests
+    pub(crate) trait Encoding {
+        fn to_vec<T: Serialize>(value: &T) -> Vec<u8>;
+        fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> T;
+        fn parse_message(bytes: &[u8]) -> Result<Message, ParseError>;
+    }
+
+    fn messages() -> Vec<JsonRpc> {
+        vec![
+            JsonRpc::request(1, "a"),
+            JsonRpc::request_with_params(
+                String::from("x-1"),
+                "b",
+                json!([1, -2.5, "c", null, true]),
+            ),
+            JsonRpc::request_with_params((), "c", json!({"d": {"e": [1, 2]}})),
+            JsonRpc::notification_with_params("d", json!([])),
+            JsonRpc::notification_with_params("d", json!({})),
+            JsonRpc::success(u64::MAX, &json!({"f": [1.5, "g"]})),
+            JsonRpc::success(i64::MIN, &Value::Null),
+            JsonRpc::error(3, RpcError::invalid_params().with_data(json!({"h": -1}))),
+            JsonRpc::error((), RpcError::parse_error()),
+        ]
+    }
+
+    /// Checks that messages and their parts survive a round trip, and that
+    /// the encoded form decodes to the same JSON value as the JSON form
+    pub(crate) fn round_trips<E: Encoding>() {
+        for message in messages() {
+            let bytes = E::to_vec(&message);
+            assert_eq!(E::from_slice::<JsonRpc>(&bytes), message);
+            assert_eq!(
+                E::from_slice::<Value>(&bytes),
+                serde_json::to_value(&message).unwrap()
+            );
+        }
+
+        let ids = vec![Id::from(-7), Id::from(String::from("id")), Id::None(())];
+        assert_eq!(E::from_slice::<Vec<Id>>(&E::to_vec(&ids)), ids);
+
+        let params = vec![
+            Params::from(json!([1, 2])),
+            Params::from(json!({"a": 1})),
+            Params::None(()),
+        ];
+        assert_eq!(E::from_slice::<Vec<Params>>(&E::to_vec(&params)), params);
+
+        let error = RpcError::internal_error().with_data("i");
+        assert_eq!(E::from_slice::<RpcError>(&E::to_vec(&error)), error);
+
+        // Numbers are encoded the same as plain Rust integers, whatever the
+        // serde_json features
+        #[derive(Serialize)]
+        struct Plain {
+            jsonrpc: &'static str,
+            method: &'static str,
+            params: [u8; 1],
+            id: u8,
+        }
+        assert_eq!(
+            E::to_vec(&JsonRpc::request_with_params(7, "a", json!([1]))),
+            E::to_vec(&Plain {
+                jsonrpc: "2.0",
+                method: "a",
+                params: [1],
+                id: 7,
+            })
+        );
+    }
+
+    /// Checks that payloads decode to messages, with spec errors on failure
+    pub(crate) fn parses_messages<E: Encoding>() {
+        let batch = messages();
+        assert_eq!(
+            E::parse_message(&E::to_vec(&batch)),
+            Ok(Message::from(batch))
+        );
+        let single = JsonRpc::notification("a");
+        assert_eq!(
+            E::parse_message(&E::to_vec(&single)),
+            Ok(Message::from(single))
+        );
+
+        let invalid = E::to_vec(&json!({"jsonrpc": "2.0", "method": 1, "id": 4}));
+        let err = E::parse_message(&invalid).unwrap_err();
+        assert_eq!((err.code(), err.id), (-32600, Some(Id::from(4))));
+    }
+}
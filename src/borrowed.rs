@@ -0,0 +1,492 @@
+//! Borrowed JSON-RPC 2.0 messages
+//!
+//! This module provides zero-copy variants of the message types in [`crate::jsonrpc`].
+//! The method name borrows from the input, and params, results and errors are kept
+//! as unparsed [`RawValue`] slices of the input, so a message can be routed by its
+//! `method` and `id` and forwarded untouched without building a `Value` tree.
+//!
+//! A borrowed message converts to the owned [`JsonRpc`] when its payload is needed.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::{Result as SerdeResult, Value};
+
+use crate::jsonrpc::{self, read_message, Id, JsonRpc, Params, ParseOptions, Parts, Payload};
+use crate::Error as RpcError;
+
+/// Borrowed JSON-RPC 2.0 Request object
+///
+/// The params are kept as raw JSON borrowed from the input.
+#[derive(Clone, Debug, Serialize)]
+pub struct RequestRef<'a> {
+    /// JSON-RPC protocol version (always "2.0")
+    jsonrpc: Cow<'a, str>,
+    /// Name of the method to be invoked
+    pub method: Cow<'a, str>,
+    /// Raw parameters to be used during the invocation of the method
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<&'a RawValue>,
+    /// Client-established identifier for this request
+    pub id: Id,
+}
+
+/// Borrowed JSON-RPC 2.0 Notification object
+///
+/// The params are kept as raw JSON borrowed from the input.
+#[derive(Clone, Debug, Serialize)]
+pub struct NotificationRef<'a> {
+    /// JSON-RPC protocol version (always "2.0")
+    jsonrpc: Cow<'a, str>,
+    /// Name of the method to be invoked
+    pub method: Cow<'a, str>,
+    /// Raw parameters to be used during the invocation of the method
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<&'a RawValue>,
+}
+
+/// Borrowed JSON-RPC 2.0 Success Response object
+///
+/// The result is kept as raw JSON borrowed from the input.
+#[derive(Clone, Debug, Serialize)]
+pub struct SuccessRef<'a> {
+    /// JSON-RPC protocol version (always "2.0")
+    jsonrpc: Cow<'a, str>,
+    /// The raw result of the method call
+    pub result: &'a RawValue,
+    /// Client-established identifier matching the request
+    pub id: Id,
+}
+
+/// Borrowed JSON-RPC 2.0 Error Response object
+///
+/// The error object is kept as raw JSON borrowed from the input.
+#[derive(Clone, Debug, Serialize)]
+pub struct ErrorRef<'a> {
+    /// JSON-RPC protocol version (always "2.0")
+    jsonrpc: Cow<'a, str>,
+    /// The raw error object
+    pub error: &'a RawValue,
+    /// Client-established identifier matching the request
+    pub id: Id,
+}
+
+/// Borrowed JSON-RPC 2.0 Request object and Response object
+///
+/// The borrowed counterpart of [`JsonRpc`]. The method name is borrowed from the
+/// input unless it contains escape sequences, and params, results and errors
+/// are raw JSON slices of the input. Serializing a borrowed message writes the
+/// raw slices verbatim.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum JsonRpcRef<'a> {
+    /// Request object
+    Request(RequestRef<'a>),
+    /// Notification object
+    Notification(NotificationRef<'a>),
+    /// Success Response
+    Success(SuccessRef<'a>),
+    /// Error Response
+    Error(ErrorRef<'a>),
+}
+
+impl<'a> JsonRpcRef<'a> {
+    /// Parses a JSON string into a borrowed JSON-RPC message
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse, which the message borrows from
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpcRef or a serde_json error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::JsonRpcRef;
+    ///
+    /// let input = r#"{"jsonrpc":"2.0","method":"subtract","params":[42, 23],"id":1}"#;
+    /// let request = JsonRpcRef::parse(input).unwrap();
+    /// assert_eq!(request.get_method(), Some("subtract"));
+    /// assert_eq!(request.get_params().unwrap().get(), "[42, 23]");
+    /// ```
+    pub fn parse(input: &'a str) -> SerdeResult<Self> {
+        serde_json::from_str(input)
+    }
+
+    /// Parses a JSON string into a borrowed JSON-RPC message, applying the given
+    /// conformance checks
+    ///
+    /// The checks are the same as for [`JsonRpc::parse_with`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse, which the message borrows from
+    /// * `options` - The conformance checks to apply
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpcRef or a serde_json error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{JsonRpcRef, ParseOptions};
+    ///
+    /// let input = r#"{"jsonrpc":"2.0","method":"ping","trace":"abc"}"#;
+    /// assert!(JsonRpcRef::parse(input).is_ok());
+    /// assert!(JsonRpcRef::parse_with(input, &ParseOptions::strict()).is_err());
+    /// ```
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> SerdeResult<Self> {
+        let mut deserializer = serde_json::Deserializer::from_str(input);
+        let message = deserializer.deserialize_map(JsonRpcRefVisitor { options })?;
+        deserializer.end()?;
+        Ok(message)
+    }
+
+    /// Parses a JSON string into a vector of borrowed JSON-RPC messages
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse, which the messages borrow from
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either a vector of parsed JsonRpcRef objects or a serde_json error
+    pub fn parse_vec(input: &'a str) -> SerdeResult<Vec<Self>> {
+        serde_json::from_str(input)
+    }
+
+    /// Gets the JSON-RPC protocol version
+    ///
+    /// # Returns
+    ///
+    /// The protocol version string ("2.0")
+    pub fn get_version(&self) -> &str {
+        match self {
+            JsonRpcRef::Request(ref v) => &v.jsonrpc,
+            JsonRpcRef::Notification(ref v) => &v.jsonrpc,
+            JsonRpcRef::Success(ref v) => &v.jsonrpc,
+            JsonRpcRef::Error(ref v) => &v.jsonrpc,
+        }
+    }
+
+    /// Gets the identifier from the JSON-RPC message
+    ///
+    /// # Returns
+    ///
+    /// The identifier if present (for requests and responses), or None for notifications
+    pub fn get_id(&self) -> Option<&Id> {
+        match *self {
+            JsonRpcRef::Request(ref v) => Some(&v.id),
+            JsonRpcRef::Success(ref v) => Some(&v.id),
+            JsonRpcRef::Error(ref v) => Some(&v.id),
+            JsonRpcRef::Notification(_) => None,
+        }
+    }
+
+    /// Gets the method name from the JSON-RPC message
+    ///
+    /// # Returns
+    ///
+    /// The method name if present (for requests and notifications), or None for responses
+    pub fn get_method(&self) -> Option<&str> {
+        match *self {
+            JsonRpcRef::Request(ref v) => Some(&v.method),
+            JsonRpcRef::Notification(ref v) => Some(&v.method),
+            _ => None,
+        }
+    }
+
+    /// Gets the raw parameters from the JSON-RPC message
+    ///
+    /// # Returns
+    ///
+    /// The raw parameters if present (for requests and notifications), or None otherwise
+    pub fn get_params(&self) -> Option<&'a RawValue> {
+        match *self {
+            JsonRpcRef::Request(ref v) => v.params,
+            JsonRpcRef::Notification(ref v) => v.params,
+            _ => None,
+        }
+    }
+
+    /// Gets the raw result from a successful JSON-RPC response
+    ///
+    /// # Returns
+    ///
+    /// The raw result if this is a success response, or None otherwise
+    pub fn get_result(&self) -> Option<&'a RawValue> {
+        match *self {
+            JsonRpcRef::Success(ref v) => Some(v.result),
+            _ => None,
+        }
+    }
+
+    /// Gets the raw error object from an error JSON-RPC response
+    ///
+    /// # Returns
+    ///
+    /// The raw error object if this is an error response, or None otherwise
+    pub fn get_error(&self) -> Option<&'a RawValue> {
+        match *self {
+            JsonRpcRef::Error(ref v) => Some(v.error),
+            _ => None,
+        }
+    }
+
+    /// Converts the borrowed message into an owned JSON-RPC message
+    ///
+    /// The raw params, result or error are parsed at this point.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the owned JsonRpc or a serde_json error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{JsonRpc, JsonRpcRef};
+    /// use serde_json::json;
+    ///
+    /// let input = r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}"#;
+    /// let request = JsonRpcRef::parse(input).unwrap();
+    /// assert_eq!(
+    ///     request.to_json_rpc().unwrap(),
+    ///     JsonRpc::request_with_params(1, "subtract", json!([42, 23]))
+    /// );
+    /// ```
+    pub fn to_json_rpc(&self) -> SerdeResult<JsonRpc> {
+        let params = |params: Option<&RawValue>| {
+            params
+                .map(|raw| serde_json::from_str::<Params>(raw.get()))
+                .transpose()
+        };
+        Ok(match *self {
+            JsonRpcRef::Request(ref v) => JsonRpc::Request(jsonrpc::Request {
+                jsonrpc: v.jsonrpc.clone().into_owned(),
+                method: v.method.clone().into_owned(),
+                params: params(v.params)?,
+                id: v.id.clone(),
+            }),
+            JsonRpcRef::Notification(ref v) => JsonRpc::Notification(jsonrpc::Notification {
+                jsonrpc: v.jsonrpc.clone().into_owned(),
+                method: v.method.clone().into_owned(),
+                params: params(v.params)?,
+            }),
+            JsonRpcRef::Success(ref v) => JsonRpc::Success(jsonrpc::Success {
+                jsonrpc: v.jsonrpc.clone().into_owned(),
+                result: serde_json::from_str::<Value>(v.result.get())?,
+                id: v.id.clone(),
+            }),
+            JsonRpcRef::Error(ref v) => JsonRpc::Error(jsonrpc::Error {
+                jsonrpc: v.jsonrpc.clone().into_owned(),
+                error: serde_json::from_str::<RpcError>(v.error.get())?,
+                id: v.id.clone(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<JsonRpcRef<'_>> for JsonRpc {
+    type Error = serde_json::Error;
+
+    /// Converts a borrowed message into an owned JSON-RPC message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The borrowed message to convert
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the owned JsonRpc or a serde_json error
+    fn try_from(message: JsonRpcRef<'_>) -> SerdeResult<Self> {
+        message.to_json_rpc()
+    }
+}
+
+impl Payload for &RawValue {
+    fn check_params(&self) -> Result<(), String> {
+        match self.get().trim_start().as_bytes().first() {
+            // Any array or object makes valid params, so it is not parsed
+            Some(b'[') | Some(b'{') => Ok(()),
+            _ => serde_json::from_str::<Params>(self.get())
+                .map(drop)
+                .map_err(jsonrpc::error_message),
+        }
+    }
+
+    fn check_error(&self) -> Result<(), String> {
+        serde_json::from_str::<shape::Error>(self.get())
+            .map(drop)
+            .map_err(jsonrpc::error_message)
+    }
+}
+
+mod shape {
+    use std::borrow::Cow;
+
+    use serde::de::IgnoredAny;
+    use serde::Deserialize;
+
+    /// The members of an error object, checked like [`crate::Error`] without
+    /// building the message and data
+    ///
+    /// It shares the name of the owned type, which appears in error messages.
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    pub(super) struct Error<'a> {
+        code: i64,
+        #[serde(borrow)]
+        message: Cow<'a, str>,
+        data: Option<IgnoredAny>,
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for JsonRpcRef<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(JsonRpcRefVisitor {
+            options: &ParseOptions::default(),
+        })
+    }
+}
+
+/// Reads the members of a message object once, keeping payloads as raw JSON
+struct JsonRpcRefVisitor<'a> {
+    options: &'a ParseOptions,
+}
+
+impl<'de> Visitor<'de> for JsonRpcRefVisitor<'_> {
+    type Value = JsonRpcRef<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON-RPC 2.0 message object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        Ok(match read_message(map, self.options, None)? {
+            Parts::Request {
+                jsonrpc,
+                method,
+                params,
+                id,
+            } => JsonRpcRef::Request(RequestRef {
+                jsonrpc,
+                method,
+                params,
+                id,
+            }),
+            Parts::Notification {
+                jsonrpc,
+                method,
+                params,
+            } => JsonRpcRef::Notification(NotificationRef {
+                jsonrpc,
+                method,
+                params,
+            }),
+            Parts::Success {
+                jsonrpc,
+                result,
+                id,
+            } => JsonRpcRef::Success(SuccessRef {
+                jsonrpc,
+                result,
+                id,
+            }),
+            Parts::Error { jsonrpc, error, id } => {
+                JsonRpcRef::Error(ErrorRef { jsonrpc, error, id })
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn borrows_from_input() {
+        let input = r#"{"jsonrpc":"2.0","method":"update","params":{"a": [1, 2]},"id":"x"}"#;
+        let message = JsonRpcRef::parse(input).expect("Unable to parse borrowed request");
+        let request = match message {
+            JsonRpcRef::Request(ref request) => request,
+            _ => panic!("Expected a request"),
+        };
+        assert!(matches!(request.method, Cow::Borrowed("update")));
+        assert_eq!(request.params.map(RawValue::get), Some(r#"{"a": [1, 2]}"#));
+        assert_eq!(request.id, Id::Str(String::from("x")));
+
+        let forwarded = serde_json::to_string(&message).expect("Unable to serialize request");
+        assert_eq!(forwarded, input);
+    }
+
+    #[test]
+    fn applies_parse_options() {
+        // Raw payloads are checked once read, so their errors are reported at
+        // the end of the payload and only the error messages are compared
+        let compare = |input: &str, options: &ParseOptions| {
+            let owned = JsonRpc::parse_with(input, options).map_err(jsonrpc::error_message);
+            let borrowed = JsonRpcRef::parse_with(input, options)
+                .map(|message| message.to_json_rpc().unwrap())
+                .map_err(jsonrpc::error_message);
+            assert_eq!(borrowed, owned);
+            borrowed
+        };
+
+        for input in [
+            r#"{"jsonrpc":"2.0","method":"a","extra":1,"id":1}"#,
+            r#"{"jsonrpc":"1.0","method":"a","id":1}"#,
+            r#"{"jsonrpc":"2.0","method":"a","params":null}"#,
+            r#"{"jsonrpc":"2.0","result":1,"error":{"code":1,"message":""},"id":1}"#,
+            r#"{"jsonrpc":"2.0","method":"a","result":1,"id":1}"#,
+        ] {
+            assert!(compare(input, &ParseOptions::default()).is_ok());
+            assert!(compare(input, &ParseOptions::strict()).is_err());
+        }
+
+        for input in [
+            r#"{"jsonrpc":"2.0","method":"a","params":3,"id":1}"#,
+            r#"{"jsonrpc":"2.0","method":"a","params":"x"}"#,
+            r#"{"jsonrpc":"2.0","method":"a","params":true}"#,
+            r#"{"jsonrpc":"2.0","method":"a","params":0.5,"id":1}"#,
+            r#"{"jsonrpc":"2.0","error":-1.5,"id":1}"#,
+            r#"{"jsonrpc":"2.0","error":"failed","id":1}"#,
+            r#"{"jsonrpc":"2.0","error":[1,"x"],"id":1}"#,
+            r#"{"jsonrpc":"2.0","error":{"foo":1},"id":1}"#,
+            r#"{"jsonrpc":"2.0","error":{"code":1.5,"message":""},"id":1}"#,
+            r#"{"jsonrpc":"2.0","error":{"code":1,"message":"","code":2},"id":1}"#,
+        ] {
+            assert!(compare(input, &ParseOptions::default()).is_err());
+            assert!(compare(input, &ParseOptions::strict()).is_err());
+        }
+    }
+
+    #[test]
+    fn converts_to_owned() {
+        let input = r#"[
+            {"jsonrpc":"2.0","method":"note\n","params":[1]},
+            {"jsonrpc":"2.0","result":{"n":1},"id":1},
+            {"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":2}
+        ]"#;
+        let messages = JsonRpcRef::parse_vec(input).expect("Unable to parse borrowed batch");
+        let owned: Vec<JsonRpc> = messages
+            .into_iter()
+            .map(JsonRpc::try_from)
+            .collect::<SerdeResult<_>>()
+            .expect("Unable to convert borrowed messages");
+        assert_eq!(
+            owned,
+            vec![
+                JsonRpc::notification_with_params("note\n", json!([1])),
+                JsonRpc::success(1, &json!({"n": 1})),
+                JsonRpc::error(2, RpcError::method_not_found()),
+            ]
+        );
+    }
+}
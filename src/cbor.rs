@@ -0,0 +1,139 @@
+//! CBOR encoding of JSON-RPC 2.0 messages
+//!
+//! Messages are encoded as CBOR maps with the same member names as their JSON
+//! form, and decoded with the same variant detection, so a message survives a
+//! round trip through CBOR just as it does through JSON. Values that have no
+//! JSON equivalent, such as byte strings or tags, are rejected when decoding.
+
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::binary::{parse_decoded, Portable};
+use crate::error::ParseError;
+use crate::jsonrpc::Message;
+
+/// The error returned when encoding CBOR fails
+pub type EncodeError = ciborium::ser::Error<std::io::Error>;
+
+/// The error returned when decoding CBOR fails
+pub type DecodeError = ciborium::de::Error<std::io::Error>;
+
+/// Encodes a value as CBOR
+///
+/// # Arguments
+///
+/// * `value` - The value to encode, e.g. a `JsonRpc`, `Params`, `Id` or `Error`
+///
+/// # Returns
+///
+/// The CBOR bytes, or the error encountered
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::{cbor, JsonRpc};
+///
+/// let request = JsonRpc::request(1, "ping");
+/// let bytes = cbor::to_vec(&request).unwrap();
+/// assert_eq!(cbor::from_slice::<JsonRpc>(&bytes).unwrap(), request);
+/// ```
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut bytes = Vec::new();
+    to_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Encodes a value as CBOR into a writer
+///
+/// # Arguments
+///
+/// * `value` - The value to encode
+/// * `writer` - The stream to write the CBOR bytes to
+///
+/// # Returns
+///
+/// `Ok(())` once written, or the error encountered
+pub fn to_writer<T: Serialize + ?Sized, W: Write>(value: &T, writer: W) -> Result<(), EncodeError> {
+    ciborium::into_writer(&Portable(value), writer)
+}
+
+/// Decodes a value from CBOR bytes
+///
+/// # Arguments
+///
+/// * `bytes` - The CBOR bytes to decode
+///
+/// # Returns
+///
+/// The decoded value, or the error encountered
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    ciborium::from_reader(bytes)
+}
+
+/// Decodes a value from a stream of CBOR bytes
+///
+/// # Arguments
+///
+/// * `reader` - The stream to read the CBOR bytes from
+///
+/// # Returns
+///
+/// The decoded value, or the error encountered
+pub fn from_reader<T: DeserializeOwned, R: Read>(reader: R) -> Result<T, DecodeError> {
+    ciborium::from_reader(reader)
+}
+
+/// Decodes a single message or a batch from CBOR bytes, mapping failures to spec errors
+///
+/// Undecodable bytes yield a parse error (-32700), and a value that is not a
+/// valid payload yields an invalid request error (-32600), as with
+/// [`JsonRpc::parse_message`](crate::JsonRpc::parse_message).
+///
+/// # Arguments
+///
+/// * `bytes` - The CBOR bytes to decode
+///
+/// # Returns
+///
+/// A Result containing either the decoded Message or a ParseError
+pub fn parse_message(bytes: &[u8]) -> Result<Message, ParseError> {
+    parse_decoded(ciborium::from_reader(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::tests::{self, Encoding};
+
+    struct Cbor;
+
+    impl Encoding for Cbor {
+        fn to_vec<T: Serialize>(value: &T) -> Vec<u8> {
+            to_vec(value).expect("Unable to encode value")
+        }
+
+        fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> T {
+            from_slice(bytes).expect("Unable to decode value")
+        }
+
+        fn parse_message(bytes: &[u8]) -> Result<Message, ParseError> {
+            parse_message(bytes)
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        tests::round_trips::<Cbor>();
+    }
+
+    #[test]
+    fn parses_messages() {
+        tests::parses_messages::<Cbor>();
+
+        // A byte string has no JSON equivalent
+        assert_eq!(parse_message(&[0x41, 0x00]).unwrap_err().code(), -32700);
+        assert_eq!(parse_message(&[0xff, 0x00]).unwrap_err().code(), -32700);
+    }
+}
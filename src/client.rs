@@ -0,0 +1,602 @@
+//! Transport-agnostic JSON-RPC 2.0 client core
+//!
+//! This module provides a `Client` that allocates request ids, keeps track of
+//! the requests awaiting a response, and resolves them when the matching
+//! response arrives. The client does not send or receive anything itself:
+//! the caller writes the requests it builds to a transport, and feeds the
+//! responses read from that transport back to it.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll};
+
+use futures_channel::oneshot;
+use serde_json::Value;
+
+use crate::id::{IdGenerator, SequentialIds};
+use crate::jsonrpc::{Id, JsonRpc, Message, Params, Request};
+use crate::{Error as RpcError, ParseError};
+
+/// The number of completed request ids remembered to detect duplicate responses
+const COMPLETED_CAPACITY: usize = 1024;
+
+/// Errors reported by the client
+#[derive(Clone, PartialEq, Debug)]
+pub enum ClientError {
+    /// The server answered the request with an error object
+    Rpc(RpcError),
+    /// The request was canceled, or the client dropped, before a response arrived
+    Canceled,
+    /// A response arrived for an id that no request is waiting for
+    UnmatchedId(Id),
+    /// A response arrived for an id that was already answered
+    DuplicateId(Id),
+    /// The id generator yielded an id that a pending request already uses
+    IdInUse(Id),
+    /// A response could not be parsed, e.g. a malformed entry of a batch
+    Malformed(ParseError),
+    /// A request or notification was passed where a response was expected
+    UnexpectedMessage,
+}
+
+impl fmt::Display for ClientError {
+    /// Formats the client error as a string
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter to write to
+    ///
+    /// # Returns
+    ///
+    /// A `fmt::Result` indicating success or failure of the formatting operation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClientError::Rpc(ref err) => write!(f, "{}", err),
+            ClientError::Canceled => f.write_str("request canceled"),
+            ClientError::UnmatchedId(ref id) => write!(f, "unmatched response id {:?}", id),
+            ClientError::DuplicateId(ref id) => write!(f, "duplicate response id {:?}", id),
+            ClientError::IdInUse(ref id) => write!(f, "request id {:?} is already pending", id),
+            ClientError::Malformed(ref err) => write!(f, "malformed response: {}", err),
+            ClientError::UnexpectedMessage => f.write_str("expected a response message"),
+        }
+    }
+}
+
+impl error::Error for ClientError {}
+
+/// The outcome of a request: its result, or why it has none
+pub type Response = Result<Value, ClientError>;
+
+/// A callback invoked with the outcome of a request
+type Callback = Box<dyn FnOnce(Response) + Send>;
+
+/// A request awaiting its response
+enum Pending {
+    Future(oneshot::Sender<Response>),
+    Callback(Callback),
+}
+
+impl Pending {
+    fn resolve(self, response: Response) {
+        match self {
+            Pending::Future(sender) => {
+                let _ = sender.send(response);
+            }
+            Pending::Callback(callback) => callback(response),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    pending: HashMap<Id, Pending>,
+    completed: HashSet<Id>,
+    completed_order: VecDeque<Id>,
+}
+
+impl State {
+    /// Remembers an answered id, forgetting the oldest beyond the capacity
+    fn complete(&mut self, id: Id) {
+        if self.completed.insert(id.clone()) {
+            self.completed_order.push_back(id);
+        }
+        while self.completed_order.len() > COMPLETED_CAPACITY {
+            if let Some(oldest) = self.completed_order.pop_front() {
+                self.completed.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// A future resolving to the outcome of a request
+///
+/// Resolves to the `result` of a Success response, to `ClientError::Rpc` for an
+/// Error response, or to `ClientError::Canceled` if the request is canceled.
+/// Dropping the future stops waiting for the response and forgets the request.
+pub struct ResponseFuture {
+    id: Id,
+    receiver: oneshot::Receiver<Response>,
+    state: Weak<Mutex<State>>,
+}
+
+impl ResponseFuture {
+    /// Gets the id of the request this future waits for
+    ///
+    /// # Returns
+    ///
+    /// The request identifier
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+}
+
+impl Drop for ResponseFuture {
+    fn drop(&mut self) {
+        self.receiver.close();
+        let Some(state) = self.state.upgrade() else {
+            return;
+        };
+        let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+        // The id may have been answered and reused since, so only the entry
+        // feeding this future is removed
+        if let Some(Pending::Future(sender)) = state.pending.get(&self.id) {
+            if sender.is_canceled() {
+                state.pending.remove(&self.id);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ResponseFuture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl Future for ResponseFuture {
+    type Output = Response;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Response> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|response| response.unwrap_or(Err(ClientError::Canceled)))
+    }
+}
+
+/// Correlates responses with the requests awaiting them
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::{Client, JsonRpc};
+/// use serde_json::json;
+///
+/// let client = Client::new();
+/// let (request, response) = client.request_with_params("add", json!([1, 2])).unwrap();
+/// // write `request` to the transport, then feed back what is read from it
+/// client.handle_response(JsonRpc::success(request.get_id().unwrap(), &json!(3))).unwrap();
+///
+/// assert_eq!(futures::executor::block_on(response), Ok(json!(3)));
+/// ```
+pub struct Client {
+    ids: Box<dyn IdGenerator>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::with_id_generator(SequentialIds::new())
+    }
+}
+
+impl Client {
+    /// Creates a client with no pending requests
+    ///
+    /// # Returns
+    ///
+    /// A new `Client` numbering its requests from 0
+    pub fn new() -> Self {
+        Client::default()
+    }
+
+    /// Creates a client allocating request ids with the given generator
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The id generator, e.g. an `Arc` shared with the clients of previous connections
+    ///
+    /// # Returns
+    ///
+    /// A new `Client`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::id::PrefixedIds;
+    /// use jsonrpc_lite::{Client, Id};
+    ///
+    /// let client = Client::with_id_generator(PrefixedIds::new("worker-3-"));
+    /// let (request, _) = client.request("ping").unwrap();
+    /// assert_eq!(request.get_id(), Some(Id::Str(String::from("worker-3-0"))));
+    /// ```
+    pub fn with_id_generator<G: IdGenerator + 'static>(ids: G) -> Self {
+        Client {
+            ids: Box::new(ids),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Builds a request with a fresh id and registers it as pending
+    ///
+    /// An id that is still pending is refused, so that the request already
+    /// waiting for it keeps its response.
+    fn register(
+        &self,
+        method: &str,
+        params: Option<Params>,
+        pending: Pending,
+    ) -> Result<JsonRpc, ClientError> {
+        let id = self.ids.next_id();
+        let mut state = self.state();
+        match state.pending.entry(id.clone()) {
+            Entry::Occupied(_) => return Err(ClientError::IdInUse(id)),
+            Entry::Vacant(entry) => {
+                entry.insert(pending);
+            }
+        }
+        state.completed.remove(&id);
+        Ok(JsonRpc::Request(Request::new(id, method, params)))
+    }
+
+    fn request_future(
+        &self,
+        method: &str,
+        params: Option<Params>,
+    ) -> Result<(JsonRpc, ResponseFuture), ClientError> {
+        let (sender, receiver) = oneshot::channel();
+        let request = self.register(method, params, Pending::Future(sender))?;
+        let id = request.get_id().unwrap_or(Id::None(()));
+        let state = Arc::downgrade(&self.state);
+        Ok((
+            request,
+            ResponseFuture {
+                id,
+                receiver,
+                state,
+            },
+        ))
+    }
+
+    /// Builds a request without params and awaits its response
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    ///
+    /// # Returns
+    ///
+    /// The request to send, and a future resolving to its outcome, or
+    /// `ClientError::IdInUse` if the generated id is still pending
+    pub fn request(&self, method: &str) -> Result<(JsonRpc, ResponseFuture), ClientError> {
+        self.request_future(method, None)
+    }
+
+    /// Builds a request with params and awaits its response
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    /// * `params` - The parameters to pass to the method
+    ///
+    /// # Returns
+    ///
+    /// The request to send, and a future resolving to its outcome, or
+    /// `ClientError::IdInUse` if the generated id is still pending
+    pub fn request_with_params<P: Into<Params>>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<(JsonRpc, ResponseFuture), ClientError> {
+        self.request_future(method, Some(params.into()))
+    }
+
+    /// Builds a request whose outcome is passed to a callback
+    ///
+    /// The callback runs on the thread that hands the response to the client,
+    /// or that cancels the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    /// * `params` - The parameters to pass to the method, if any
+    /// * `callback` - Called once with the outcome of the request
+    ///
+    /// # Returns
+    ///
+    /// The request to send, or `ClientError::IdInUse` if the generated id is
+    /// still pending, in which case the callback is dropped without being called
+    pub fn request_with_callback<F>(
+        &self,
+        method: &str,
+        params: Option<Params>,
+        callback: F,
+    ) -> Result<JsonRpc, ClientError>
+    where
+        F: FnOnce(Response) + Send + 'static,
+    {
+        self.register(method, params, Pending::Callback(Box::new(callback)))
+    }
+
+    /// Resolves the pending request matching a response
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The response read from the transport
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the response resolved a pending request, or the protocol error
+    /// describing why it did not
+    pub fn handle_response(&self, message: JsonRpc) -> Result<(), ClientError> {
+        let (id, response) = match message {
+            JsonRpc::Success(success) => (success.id, Ok(success.result)),
+            JsonRpc::Error(error) => (error.id, Err(ClientError::Rpc(error.error))),
+            JsonRpc::Request(_) | JsonRpc::Notification(_) => {
+                return Err(ClientError::UnexpectedMessage)
+            }
+        };
+
+        self.resolve(id, response)
+    }
+
+    /// Resolves the pending request with the given id
+    fn resolve(&self, id: Id, response: Response) -> Result<(), ClientError> {
+        let pending = {
+            let mut state = self.state();
+            match state.pending.remove(&id) {
+                Some(pending) => {
+                    state.complete(id);
+                    pending
+                }
+                None if state.completed.contains(&id) => return Err(ClientError::DuplicateId(id)),
+                None => return Err(ClientError::UnmatchedId(id)),
+            }
+        };
+        pending.resolve(response);
+        Ok(())
+    }
+
+    /// Resolves the pending request a malformed response was meant for, if its
+    /// id could be recovered, with the parse error
+    fn handle_malformed(&self, err: ParseError) -> Option<ClientError> {
+        let pending = err
+            .id
+            .as_ref()
+            .and_then(|id| self.state().pending.contains_key(id).then(|| id.clone()));
+        match pending {
+            Some(id) => self.resolve(id, Err(ClientError::Malformed(err))).err(),
+            None => Some(ClientError::Malformed(err)),
+        }
+    }
+
+    /// Resolves the pending requests matching a single response or a batch of responses
+    ///
+    /// Every response of a batch is handled, even if some of them fail. A
+    /// malformed entry whose id is recovered resolves the matching request
+    /// with `ClientError::Malformed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The response payload read from the transport
+    ///
+    /// # Returns
+    ///
+    /// The protocol errors of the responses that did not resolve a pending request
+    pub fn handle_message(&self, message: Message) -> Vec<ClientError> {
+        match message {
+            Message::Single(message) => self.handle_response(message).err().into_iter().collect(),
+            Message::Batch(batch) => batch
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    Ok(message) => self.handle_response(message).err(),
+                    Err(err) => self.handle_malformed(err),
+                })
+                .collect(),
+        }
+    }
+
+    /// Cancels a pending request
+    ///
+    /// Its future or callback resolves to `ClientError::Canceled`, and a response
+    /// arriving later is reported as unmatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the request to cancel
+    ///
+    /// # Returns
+    ///
+    /// `true` if the request was pending
+    pub fn cancel(&self, id: &Id) -> bool {
+        let pending = self.state().pending.remove(id);
+        match pending {
+            Some(pending) => {
+                pending.resolve(Err(ClientError::Canceled));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels every pending request, e.g. when the transport is closed
+    ///
+    /// # Returns
+    ///
+    /// The number of requests canceled
+    pub fn cancel_all(&self) -> usize {
+        let pending: Vec<Pending> = self.state().pending.drain().map(|(_, p)| p).collect();
+        let count = pending.len();
+        for pending in pending {
+            pending.resolve(Err(ClientError::Canceled));
+        }
+        count
+    }
+
+    /// Gets the number of requests awaiting a response
+    ///
+    /// # Returns
+    ///
+    /// The number of pending requests
+    pub fn pending_count(&self) -> usize {
+        self.state().pending.len()
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("pending", &self.pending_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn resolves_by_id() {
+        let client = Client::new();
+        let (first, first_response) = client.request("a").unwrap();
+        let (second, second_response) = client.request_with_params("b", json!({"x": 1})).unwrap();
+        assert_ne!(first.get_id(), second.get_id());
+        assert_eq!(client.pending_count(), 2);
+
+        let second_id = second.get_id().expect("Request has no id");
+        client
+            .handle_response(JsonRpc::error(second_id, RpcError::invalid_params()))
+            .expect("Unable to handle error response");
+        client
+            .handle_response(JsonRpc::success(first_response.id().clone(), &json!("ok")))
+            .expect("Unable to handle success response");
+
+        assert_eq!(block_on(first_response), Ok(json!("ok")));
+        assert_eq!(
+            block_on(second_response),
+            Err(ClientError::Rpc(RpcError::invalid_params()))
+        );
+        assert_eq!(client.pending_count(), 0);
+    }
+
+    #[test]
+    fn reports_protocol_errors() {
+        let client = Client::new();
+        let outcome = Arc::new(Mutex::new(None));
+        let slot = outcome.clone();
+        let request = client
+            .request_with_callback("a", None, move |response| {
+                *slot.lock().unwrap() = Some(response);
+            })
+            .unwrap();
+        let id = request.get_id().expect("Request has no id");
+
+        let responses = Message::from(vec![
+            JsonRpc::success(id.clone(), &json!(1)),
+            JsonRpc::success(id.clone(), &json!(2)),
+            JsonRpc::success(99, &json!(3)),
+            JsonRpc::request(1, "a"),
+        ]);
+        assert_eq!(
+            client.handle_message(responses),
+            vec![
+                ClientError::DuplicateId(id),
+                ClientError::UnmatchedId(Id::from(99)),
+                ClientError::UnexpectedMessage,
+            ]
+        );
+        assert_eq!(*outcome.lock().unwrap(), Some(Ok(json!(1))));
+    }
+
+    #[test]
+    fn resolves_malformed_batch_entries() {
+        let client = Client::new();
+        let (request, response) = client.request("a").unwrap();
+        let id = request.get_id().expect("Request has no id");
+
+        let input = format!(
+            r#"[{{"jsonrpc":"2.0","result":1,"error":"x","id":{}}},{{"foo":1}}]"#,
+            serde_json::to_string(&id).unwrap()
+        );
+        let errors = client.handle_message(JsonRpc::parse_message(&input).unwrap());
+        assert!(matches!(errors[..], [ClientError::Malformed(ref err)] if err.id.is_none()));
+        assert!(matches!(
+            block_on(response),
+            Err(ClientError::Malformed(ref err)) if err.id == Some(id)
+        ));
+        assert_eq!(client.pending_count(), 0);
+    }
+
+    #[test]
+    fn refuses_pending_ids() {
+        struct Repeat;
+
+        impl IdGenerator for Repeat {
+            fn next_id(&self) -> Id {
+                Id::from(7)
+            }
+        }
+
+        let client = Client::with_id_generator(Repeat);
+        let (request, response) = client.request("a").unwrap();
+        assert_eq!(
+            client.request("b").map(|(request, _)| request),
+            Err(ClientError::IdInUse(Id::from(7)))
+        );
+        assert_eq!(client.pending_count(), 1);
+
+        client
+            .handle_response(JsonRpc::success(request.get_id().unwrap(), &json!(1)))
+            .expect("Unable to handle success response");
+        assert_eq!(block_on(response), Ok(json!(1)));
+        assert!(client.request("c").is_ok());
+    }
+
+    #[test]
+    fn dropped_future_forgets_request() {
+        let client = Client::new();
+        let (_, response) = client.request("a").unwrap();
+        let (_, kept) = client.request("b").unwrap();
+        assert_eq!(client.pending_count(), 2);
+        drop(response);
+        assert_eq!(client.pending_count(), 1);
+        assert!(client.cancel(kept.id()));
+    }
+
+    #[test]
+    fn cancel() {
+        let client = Client::new();
+        let (request, response) = client.request("a").unwrap();
+        let id = request.get_id().expect("Request has no id");
+        assert!(client.cancel(&id));
+        assert!(!client.cancel(&id));
+        assert_eq!(block_on(response), Err(ClientError::Canceled));
+        assert_eq!(
+            client.handle_response(JsonRpc::success(id.clone(), &json!(1))),
+            Err(ClientError::UnmatchedId(id))
+        );
+    }
+}
@@ -10,6 +10,8 @@ use std::error;
 use std::fmt;
 use std::result;
 
+use crate::jsonrpc::{Id, JsonRpc};
+
 /// Standard JSON-RPC 2.0 error codes
 ///
 /// This enum represents the predefined error codes as specified in the JSON-RPC 2.0
@@ -186,6 +188,30 @@ impl Error {
     pub fn internal_error() -> Self {
         Self::new(ErrorCode::InternalError)
     }
+
+    /// Attaches additional information to the error
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The additional error data
+    ///
+    /// # Returns
+    ///
+    /// The `Error` with its `data` field set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::Error;
+    /// use serde_json::json;
+    ///
+    /// let error = Error::invalid_params().with_data("missing field `a`");
+    /// assert_eq!(error.data, Some(json!("missing field `a`")));
+    /// ```
+    pub fn with_data<D: Into<Value>>(mut self, data: D) -> Self {
+        self.data = Some(data.into());
+        self
+    }
 }
 
 impl error::Error for Error {
@@ -218,6 +244,99 @@ impl fmt::Display for Error {
     }
 }
 
+/// A failure to parse an incoming JSON-RPC message
+///
+/// Pairs the error object a server must answer with — a parse error (-32700)
+/// for malformed JSON, or an invalid request error (-32600) for valid JSON that
+/// is not a valid message — with the `id` of the offending message, when one
+/// could be read.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    /// The error object to answer with
+    pub error: Error,
+    /// The identifier recovered from the offending message, if any
+    pub id: Option<Id>,
+}
+
+impl ParseError {
+    /// Creates a new ParseError
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error object to answer with
+    /// * `id` - The identifier recovered from the offending message, if any
+    ///
+    /// # Returns
+    ///
+    /// A new `ParseError` instance
+    pub fn new(error: Error, id: Option<Id>) -> Self {
+        ParseError { error, id }
+    }
+
+    /// Gets the error code of the failure
+    ///
+    /// # Returns
+    ///
+    /// The numeric JSON-RPC error code
+    pub fn code(&self) -> i64 {
+        self.error.code
+    }
+
+    /// Turns the failure into the error response required by the specification
+    ///
+    /// The response echoes the recovered `id`, or `null` if none could be read.
+    ///
+    /// # Returns
+    ///
+    /// A new JsonRpc::Error variant
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{Error, Id, JsonRpc};
+    ///
+    /// let err = JsonRpc::try_parse(r#"{"jsonrpc":"2.0","id":1}"#).unwrap_err();
+    /// let response = err.into_response();
+    /// assert_eq!(response.get_id(), Some(Id::from(1)));
+    /// assert_eq!(response.get_error().unwrap().code, Error::invalid_request().code);
+    /// ```
+    pub fn into_response(self) -> JsonRpc {
+        JsonRpc::error(self.id.unwrap_or(Id::None(())), self.error)
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    /// Formats the underlying error object
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter to write to
+    ///
+    /// # Returns
+    ///
+    /// A `fmt::Result` indicating success or failure of the formatting operation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl From<ParseError> for Error {
+    /// Extracts the error object from a ParseError
+    ///
+    /// # Arguments
+    ///
+    /// * `err` - The ParseError to convert
+    ///
+    /// # Returns
+    ///
+    /// The error object carried by the ParseError
+    fn from(err: ParseError) -> Self {
+        err.error
+    }
+}
+
 /// A type alias for `Result<T, Error>`
 ///
 /// This is a convenience type that represents either a successful result of type `T`
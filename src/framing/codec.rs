@@ -0,0 +1,394 @@
+//! `tokio_util::codec` implementations of the framing codecs
+//!
+//! Decoders yield `Result<Message, ParseError>` items: a payload that fails to
+//! parse is handed to the caller, who can answer it with
+//! `ParseError::into_response`, while the stream goes on. Errors of the
+//! decoders themselves, such as an oversized frame or a malformed header, end
+//! the `Framed` stream.
+
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use serde::Serialize;
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::header::{check_content_type, invalid_header, parse_header, MAX_HEADER_LENGTH};
+use super::length::PREFIX_LENGTH;
+use super::{parse_payload, FrameError, HeaderCodec, LengthPrefixedCodec, LineCodec};
+use crate::error::ParseError;
+use crate::jsonrpc::{JsonRpc, Message};
+
+/// The item produced by the decoders
+type Decoded = Result<Message, ParseError>;
+
+/// Serializes a payload at the end of a buffer
+fn write_json<T: Serialize>(item: &T, dst: &mut BytesMut) -> Result<(), FrameError> {
+    serde_json::to_writer(dst.writer(), item).map_err(io::Error::from)?;
+    Ok(())
+}
+
+/// Appends a frame to a buffer, removing whatever was written of it on failure
+fn write_frame<F>(dst: &mut BytesMut, write: F) -> Result<(), FrameError>
+where
+    F: FnOnce(&mut BytesMut, usize) -> Result<(), FrameError>,
+{
+    let start = dst.len();
+    let written = write(dst, start);
+    if written.is_err() {
+        dst.truncate(start);
+    }
+    written
+}
+
+/// Fails on bytes left over at the end of the stream, unless they are blank
+fn finish(src: &mut BytesMut) -> Result<Option<Decoded>, FrameError> {
+    if src.trim_ascii().is_empty() {
+        src.clear();
+        Ok(None)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "truncated frame at the end of the stream",
+        )
+        .into())
+    }
+}
+
+impl LineCodec {
+    /// Creates a decoder of newline-delimited messages
+    ///
+    /// # Returns
+    ///
+    /// A new `LineDecoder` with this codec's maximum line length
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::BytesMut;
+    /// use jsonrpc_lite::framing::LineCodec;
+    /// use jsonrpc_lite::{JsonRpc, Message};
+    /// use tokio_util::codec::Decoder;
+    ///
+    /// let mut decoder = LineCodec::new().decoder();
+    /// let mut buffer = BytesMut::from(&b"{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n"[..]);
+    /// let message = decoder.decode(&mut buffer).unwrap();
+    /// assert_eq!(message, Some(Ok(Message::Single(JsonRpc::notification("ping")))));
+    /// ```
+    pub fn decoder(self) -> LineDecoder {
+        LineDecoder {
+            codec: self,
+            next_index: 0,
+        }
+    }
+
+    fn encode_item<T: Serialize>(
+        &mut self,
+        item: &T,
+        dst: &mut BytesMut,
+    ) -> Result<(), FrameError> {
+        write_frame(dst, |dst, _| {
+            write_json(item, dst)?;
+            dst.put_u8(b'\n');
+            Ok(())
+        })
+    }
+}
+
+/// Decodes newline-delimited messages, for use with `FramedRead` or `Framed`
+///
+/// Also encodes messages like its `LineCodec`.
+#[derive(Clone, Debug)]
+pub struct LineDecoder {
+    codec: LineCodec,
+    /// Where to resume looking for a newline in the buffer
+    next_index: usize,
+}
+
+impl LineDecoder {
+    /// Gets the codec this decoder was created from
+    ///
+    /// # Returns
+    ///
+    /// The `LineCodec` configuration
+    pub fn codec(&self) -> LineCodec {
+        self.codec
+    }
+
+    fn max_length(&self) -> usize {
+        self.codec.max_length()
+    }
+}
+
+impl Decoder for LineDecoder {
+    type Item = Decoded;
+    type Error = FrameError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Decoded>, FrameError> {
+        loop {
+            // Bytes before `next_index` were already searched by previous calls
+            let newline = match src[self.next_index..].iter().position(|&b| b == b'\n') {
+                Some(offset) => self.next_index + offset,
+                None if src.len() > self.max_length().saturating_add(1) => {
+                    self.next_index = 0;
+                    return Err(FrameError::TooLarge {
+                        limit: self.max_length(),
+                    });
+                }
+                None => {
+                    self.next_index = src.len();
+                    return Ok(None);
+                }
+            };
+            self.next_index = 0;
+
+            let line = src.split_to(newline + 1);
+            let mut line = &line[..newline];
+            if line.last() == Some(&b'\r') {
+                line = &line[..line.len() - 1];
+            }
+            if line.len() > self.max_length() {
+                return Err(FrameError::TooLarge {
+                    limit: self.max_length(),
+                });
+            }
+            let line = line.trim_ascii();
+            if !line.is_empty() {
+                return Ok(Some(parse_payload(line)));
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Decoded>, FrameError> {
+        if let Some(item) = self.decode(src)? {
+            return Ok(Some(item));
+        }
+        self.next_index = 0;
+        let line = src.split();
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            Ok(None)
+        } else if line.len() > self.max_length() {
+            Err(FrameError::TooLarge {
+                limit: self.max_length(),
+            })
+        } else {
+            Ok(Some(parse_payload(line)))
+        }
+    }
+}
+
+impl Encoder<JsonRpc> for LineCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: JsonRpc, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.encode_item(&item, dst)
+    }
+}
+
+impl Encoder<Message> for LineCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.encode_item(&item, dst)
+    }
+}
+
+impl Encoder<JsonRpc> for LineDecoder {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: JsonRpc, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.codec.encode_item(&item, dst)
+    }
+}
+
+impl Encoder<Message> for LineDecoder {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.codec.encode_item(&item, dst)
+    }
+}
+
+impl HeaderCodec {
+    fn encode_item<T: Serialize>(
+        &mut self,
+        item: &T,
+        dst: &mut BytesMut,
+    ) -> Result<(), FrameError> {
+        write_frame(dst, |dst, start| {
+            // The headers need the body length, so the body is written first
+            // and the headers are put in front of it
+            write_json(item, dst)?;
+            let body = dst.split_off(start);
+            dst.put_slice(format!("Content-Length: {}\r\n", body.len()).as_bytes());
+            if let Some(content_type) = self.content_type() {
+                dst.put_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+            }
+            dst.put_slice(b"\r\n");
+            dst.unsplit(body);
+            Ok(())
+        })
+    }
+}
+
+/// Drops the blank lines tolerated between messages from the start of a buffer,
+/// so that a run of them is not searched again by every call
+fn skip_blank_lines(src: &mut BytesMut) {
+    loop {
+        let blank = match src[..] {
+            [b'\n', ..] => 1,
+            [b'\r', b'\n', ..] => 2,
+            _ => return,
+        };
+        src.advance(blank);
+    }
+}
+
+/// Parses the headers at the start of a buffer, after any blank lines
+///
+/// Returns the length of the headers, including the blank line ending them,
+/// and the body length, or None if the headers are incomplete.
+fn parse_headers(src: &[u8]) -> Result<Option<(usize, usize)>, FrameError> {
+    let mut position = 0;
+    let mut length = None;
+
+    loop {
+        let rest = &src[position..];
+        let newline = match rest.iter().position(|&b| b == b'\n') {
+            Some(newline) => newline,
+            None if rest.len() > MAX_HEADER_LENGTH => {
+                return Err(invalid_header("header line is too long"))
+            }
+            None => return Ok(None),
+        };
+        position += newline + 1;
+
+        let mut line = &rest[..newline];
+        if line.last() == Some(&b'\r') {
+            line = &line[..line.len() - 1];
+        }
+        if line.len() > MAX_HEADER_LENGTH {
+            return Err(invalid_header("header line is too long"));
+        }
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some(value) = parse_header(line, &mut length)? {
+            check_content_type(value)?;
+        }
+    }
+
+    match length {
+        Some(length) => Ok(Some((position, length))),
+        None => Err(invalid_header("missing Content-Length header")),
+    }
+}
+
+impl Decoder for HeaderCodec {
+    type Item = Decoded;
+    type Error = FrameError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Decoded>, FrameError> {
+        skip_blank_lines(src);
+        let (headers, length) = match parse_headers(src)? {
+            Some(lengths) => lengths,
+            None => return Ok(None),
+        };
+        if length > self.max_length() {
+            return Err(FrameError::TooLarge {
+                limit: self.max_length(),
+            });
+        }
+        if src.len() < headers + length {
+            src.reserve(headers + length - src.len());
+            return Ok(None);
+        }
+
+        src.advance(headers);
+        let body = src.split_to(length);
+        Ok(Some(parse_payload(&body)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Decoded>, FrameError> {
+        match self.decode(src)? {
+            Some(item) => Ok(Some(item)),
+            None => finish(src),
+        }
+    }
+}
+
+impl Encoder<JsonRpc> for HeaderCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: JsonRpc, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.encode_item(&item, dst)
+    }
+}
+
+impl Encoder<Message> for HeaderCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.encode_item(&item, dst)
+    }
+}
+
+impl LengthPrefixedCodec {
+    fn encode_item<T: Serialize>(
+        &mut self,
+        item: &T,
+        dst: &mut BytesMut,
+    ) -> Result<(), FrameError> {
+        write_frame(dst, |dst, start| {
+            dst.put_u32(0);
+            write_json(item, dst)?;
+            let length = self.check_length(dst.len() - start - PREFIX_LENGTH)?;
+            dst[start..start + PREFIX_LENGTH].copy_from_slice(&length.to_be_bytes());
+            Ok(())
+        })
+    }
+}
+
+impl Decoder for LengthPrefixedCodec {
+    type Item = Decoded;
+    type Error = FrameError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Decoded>, FrameError> {
+        if src.len() < PREFIX_LENGTH {
+            return Ok(None);
+        }
+        let mut prefix = [0; PREFIX_LENGTH];
+        prefix.copy_from_slice(&src[..PREFIX_LENGTH]);
+        let length = u32::from_be_bytes(prefix) as usize;
+        if length > self.max_length() {
+            return Err(FrameError::TooLarge {
+                limit: self.max_length(),
+            });
+        }
+        if src.len() < PREFIX_LENGTH + length {
+            src.reserve(PREFIX_LENGTH + length - src.len());
+            return Ok(None);
+        }
+
+        src.advance(PREFIX_LENGTH);
+        let payload = src.split_to(length);
+        Ok(Some(parse_payload(&payload)))
+    }
+}
+
+impl Encoder<JsonRpc> for LengthPrefixedCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: JsonRpc, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.encode_item(&item, dst)
+    }
+}
+
+impl Encoder<Message> for LengthPrefixedCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), FrameError> {
+        self.encode_item(&item, dst)
+    }
+}
@@ -0,0 +1,478 @@
+//! `Content-Length` header framing, as used by language servers and debug adapters
+
+use std::io::{self, BufRead, Read, Write};
+
+use serde::Serialize;
+
+use super::{parse_payload, read_line, FrameError, Line, DEFAULT_MAX_FRAME_LENGTH};
+use crate::jsonrpc::Message;
+
+/// The longest header line accepted, in bytes
+pub(super) const MAX_HEADER_LENGTH: usize = 4096;
+
+/// Header framing: a `Content-Length` header, an optional `Content-Type`
+/// header, a blank line, then the payload
+///
+/// ```text
+/// Content-Length: 33\r\n
+/// \r\n
+/// {"jsonrpc":"2.0","method":"ping"}
+/// ```
+///
+/// Header names are case-insensitive and unknown headers are ignored. A
+/// `Content-Type` header with a charset other than UTF-8 is rejected.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::framing::HeaderCodec;
+/// use jsonrpc_lite::{JsonRpc, Message};
+///
+/// let mut output = Vec::new();
+/// let mut writer = HeaderCodec::new().writer(&mut output);
+/// writer.write_message(&JsonRpc::notification("ping")).unwrap();
+/// assert_eq!(
+///     output,
+///     b"Content-Length: 33\r\n\r\n{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}"
+/// );
+///
+/// let mut reader = HeaderCodec::new().reader(&output[..]);
+/// let message = reader.read_message().unwrap();
+/// assert_eq!(message, Some(Message::Single(JsonRpc::notification("ping"))));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderCodec {
+    max_length: usize,
+    content_type: Option<String>,
+}
+
+impl Default for HeaderCodec {
+    fn default() -> Self {
+        HeaderCodec {
+            max_length: DEFAULT_MAX_FRAME_LENGTH,
+            content_type: None,
+        }
+    }
+}
+
+impl HeaderCodec {
+    /// Creates a codec accepting bodies up to `DEFAULT_MAX_FRAME_LENGTH` bytes
+    ///
+    /// # Returns
+    ///
+    /// A new `HeaderCodec`
+    pub fn new() -> Self {
+        HeaderCodec::default()
+    }
+
+    /// Creates a codec accepting bodies up to the given length
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The maximum length of a body, in bytes
+    ///
+    /// # Returns
+    ///
+    /// A new `HeaderCodec`
+    pub fn with_max_length(max_length: usize) -> Self {
+        HeaderCodec {
+            max_length,
+            content_type: None,
+        }
+    }
+
+    /// Adds a `Content-Type` header to written messages
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - The header value, e.g. "application/vscode-jsonrpc; charset=utf-8"
+    ///
+    /// # Returns
+    ///
+    /// The codec with the content type set
+    pub fn with_content_type<S: Into<String>>(mut self, content_type: S) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Gets the maximum length of a body
+    ///
+    /// # Returns
+    ///
+    /// The maximum length of a body, in bytes
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Gets the `Content-Type` header added to written messages
+    ///
+    /// # Returns
+    ///
+    /// The content type, or None if the header is omitted
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Creates a reader of header-framed messages
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The buffered stream to read from
+    ///
+    /// # Returns
+    ///
+    /// A new `HeaderReader`
+    pub fn reader<R: BufRead>(self, reader: R) -> HeaderReader<R> {
+        HeaderReader {
+            codec: self,
+            reader,
+            line: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Creates a writer of header-framed messages
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    ///
+    /// # Returns
+    ///
+    /// A new `HeaderWriter`
+    pub fn writer<W: Write>(self, writer: W) -> HeaderWriter<W> {
+        HeaderWriter {
+            codec: self,
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Reads header-framed messages from a buffered stream
+///
+/// Also an iterator over the messages, ending at the end of the stream.
+#[derive(Debug)]
+pub struct HeaderReader<R> {
+    codec: HeaderCodec,
+    reader: R,
+    line: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl<R: BufRead> HeaderReader<R> {
+    /// Reads the next message
+    ///
+    /// A body that is too long or that fails to parse is consumed and reported
+    /// as an error; the following messages can still be read. After a
+    /// `FrameError::InvalidHeader` the stream position is unknown and reading
+    /// should stop.
+    ///
+    /// # Returns
+    ///
+    /// The next message, None at the end of the stream, or the error encountered
+    pub fn read_message(&mut self) -> Result<Option<Message>, FrameError> {
+        let length = match self.read_headers()? {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+
+        if length > self.codec.max_length {
+            let skipped = io::copy(&mut (&mut self.reader).take(length as u64), &mut io::sink())?;
+            if skipped < length as u64 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            return Err(FrameError::TooLarge {
+                limit: self.codec.max_length,
+            });
+        }
+
+        self.body.clear();
+        self.body.resize(length, 0);
+        self.reader.read_exact(&mut self.body)?;
+        Ok(Some(parse_payload(&self.body)?))
+    }
+
+    /// Reads the headers of the next message
+    ///
+    /// Returns the body length, or None at the end of the stream.
+    fn read_headers(&mut self) -> Result<Option<usize>, FrameError> {
+        let mut length = None;
+        let mut any_header = false;
+
+        loop {
+            match read_line(&mut self.reader, &mut self.line, MAX_HEADER_LENGTH)? {
+                Line::Eof if any_header => {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+                }
+                Line::Eof => return Ok(None),
+                Line::TooLong => return Err(invalid_header("header line is too long")),
+                Line::Complete if self.line.is_empty() => {
+                    // Tolerate blank lines between messages
+                    if any_header {
+                        break;
+                    }
+                }
+                Line::Complete => {
+                    any_header = true;
+                    if let Some(value) = parse_header(&self.line, &mut length)? {
+                        check_content_type(value)?;
+                    }
+                }
+            }
+        }
+
+        length
+            .map(Some)
+            .ok_or_else(|| invalid_header("missing Content-Length header"))
+    }
+
+    /// Gets a reference to the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for HeaderReader<R> {
+    type Item = Result<Message, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message().transpose()
+    }
+}
+
+/// Parses one header line, storing a `Content-Length` value in `length`
+///
+/// Returns the value of a `Content-Type` header.
+pub(super) fn parse_header<'a>(
+    line: &'a [u8],
+    length: &mut Option<usize>,
+) -> Result<Option<&'a str>, FrameError> {
+    let line =
+        std::str::from_utf8(line).map_err(|_| invalid_header("header is not valid UTF-8"))?;
+    let (name, value) = line
+        .split_once(':')
+        .ok_or_else(|| invalid_header(format!("malformed header line {:?}", line)))?;
+    let value = value.trim();
+
+    if name.eq_ignore_ascii_case("Content-Length") {
+        if length.is_some() {
+            return Err(invalid_header("duplicate Content-Length header"));
+        }
+        let parsed = value
+            .parse()
+            .map_err(|_| invalid_header(format!("invalid Content-Length {:?}", value)))?;
+        *length = Some(parsed);
+    } else if name.eq_ignore_ascii_case("Content-Type") {
+        return Ok(Some(value));
+    }
+    Ok(None)
+}
+
+/// Rejects a `Content-Type` declaring a charset other than UTF-8
+pub(super) fn check_content_type(content_type: &str) -> Result<(), FrameError> {
+    for parameter in content_type.split(';').skip(1) {
+        if let Some((name, value)) = parameter.split_once('=') {
+            let value = value.trim().trim_matches('"');
+            if name.trim().eq_ignore_ascii_case("charset")
+                && !value.eq_ignore_ascii_case("utf-8")
+                && !value.eq_ignore_ascii_case("utf8")
+            {
+                return Err(invalid_header(format!("unsupported charset {:?}", value)));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn invalid_header<S: Into<String>>(reason: S) -> FrameError {
+    FrameError::InvalidHeader(reason.into())
+}
+
+/// Writes header-framed messages to a stream
+#[derive(Debug)]
+pub struct HeaderWriter<W> {
+    codec: HeaderCodec,
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> HeaderWriter<W> {
+    /// Writes a message with its headers and flushes the stream
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The payload to write, e.g. a `JsonRpc`, a `Message` or a `Vec<JsonRpc>`
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message is written, or the error encountered
+    pub fn write_message<T: Serialize + ?Sized>(&mut self, message: &T) -> Result<(), FrameError> {
+        self.buffer.clear();
+        serde_json::to_writer(&mut self.buffer, message).map_err(io::Error::from)?;
+
+        let mut headers = format!("Content-Length: {}\r\n", self.buffer.len());
+        if let Some(ref content_type) = self.codec.content_type {
+            headers.push_str("Content-Type: ");
+            headers.push_str(content_type);
+            headers.push_str("\r\n");
+        }
+        headers.push_str("\r\n");
+
+        self.writer.write_all(headers.as_bytes())?;
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Gets a reference to the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwraps the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonRpc;
+
+    const PING: &str = "{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}";
+
+    fn read_all(codec: HeaderCodec, input: &str) -> Vec<Result<Message, FrameError>> {
+        let mut reader = codec.reader(input.as_bytes());
+        let mut results = Vec::new();
+        loop {
+            match reader.read_message() {
+                Ok(Some(message)) => results.push(Ok(message)),
+                Ok(None) => return results,
+                Err(err) => {
+                    let recoverable = err.is_recoverable();
+                    results.push(Err(err));
+                    if !recoverable {
+                        return results;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reads_messages() {
+        let input = format!(
+            "Content-Length: 33\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}\
+             content-length:   33\nX-Extra: ignored\n\n{}",
+            PING, PING
+        );
+        let messages: Vec<_> = read_all(HeaderCodec::new(), &input)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .expect("Unable to read messages");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1], Message::Single(JsonRpc::notification("ping")));
+    }
+
+    #[test]
+    fn recovers_from_bad_bodies() {
+        let input = format!(
+            "Content-Length: 33\r\n\r\n{}Content-Length: 4\r\n\r\n{{\"a\"\
+             Content-Length: 30\r\n\r\n{{\"jsonrpc\":\"2.0\",\"method\":\"a\"}}",
+            PING
+        );
+        let mut results = read_all(HeaderCodec::with_max_length(30), &input).into_iter();
+        assert!(matches!(
+            results.next(),
+            Some(Err(FrameError::TooLarge { limit: 30 }))
+        ));
+        assert!(
+            matches!(results.next(), Some(Err(FrameError::Parse(ref err))) if err.code() == -32700)
+        );
+        assert!(matches!(
+            results.next(),
+            Some(Ok(Message::Single(ref message))) if *message == JsonRpc::notification("a")
+        ));
+        assert!(results.next().is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let cases = [
+            (
+                "Content-Type: application/json\r\n\r\n{}",
+                "missing Content-Length header",
+            ),
+            (
+                "Content-Length: -1\r\n\r\n",
+                "invalid Content-Length \"-1\"",
+            ),
+            (
+                "Content-Length 2\r\n\r\n{}",
+                "malformed header line \"Content-Length 2\"",
+            ),
+            (
+                "Content-Length: 2\r\nContent-Length: 2\r\n\r\n{}",
+                "duplicate Content-Length header",
+            ),
+            (
+                "Content-Length: 2\r\nContent-Type: application/json; charset=latin1\r\n\r\n{}",
+                "unsupported charset \"latin1\"",
+            ),
+        ];
+        for (input, reason) in cases.iter() {
+            match read_all(HeaderCodec::new(), input).pop() {
+                Some(Err(FrameError::InvalidHeader(ref found))) => assert_eq!(found, reason),
+                other => panic!(
+                    "Expected an invalid header for {:?}, got {:?}",
+                    input, other
+                ),
+            }
+        }
+
+        let truncated = read_all(HeaderCodec::new(), "Content-Length: 10\r\n\r\n{}");
+        assert!(
+            matches!(truncated[0], Err(FrameError::Io(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn writes_content_type() {
+        let codec =
+            HeaderCodec::new().with_content_type("application/vscode-jsonrpc; charset=utf-8");
+        let mut writer = codec.writer(Vec::new());
+        writer
+            .write_message(&JsonRpc::notification("ping"))
+            .expect("Unable to write message");
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            format!(
+                "Content-Length: 33\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+                PING
+            )
+        );
+    }
+}
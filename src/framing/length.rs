@@ -0,0 +1,328 @@
+//! Length-prefixed JSON-RPC framing
+
+use std::io::{self, Read, Write};
+
+use serde::Serialize;
+
+use super::{parse_payload, FrameError, DEFAULT_MAX_FRAME_LENGTH};
+use crate::jsonrpc::Message;
+
+/// The length of the frame prefix, in bytes
+pub(super) const PREFIX_LENGTH: usize = 4;
+
+/// Length-prefixed framing: a big-endian `u32` payload length, then the payload
+///
+/// Unlike line framing, payloads may contain raw newlines, e.g. when
+/// pretty-printed.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::framing::LengthPrefixedCodec;
+/// use jsonrpc_lite::{JsonRpc, Message};
+///
+/// let mut output = Vec::new();
+/// let mut writer = LengthPrefixedCodec::new().writer(&mut output);
+/// writer.write_message(&JsonRpc::notification("ping")).unwrap();
+/// assert_eq!(output[..4], [0, 0, 0, 33]);
+///
+/// let mut reader = LengthPrefixedCodec::new().reader(&output[..]);
+/// let message = reader.read_message().unwrap();
+/// assert_eq!(message, Some(Message::Single(JsonRpc::notification("ping"))));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LengthPrefixedCodec {
+    max_length: usize,
+}
+
+impl Default for LengthPrefixedCodec {
+    fn default() -> Self {
+        LengthPrefixedCodec {
+            max_length: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+}
+
+impl LengthPrefixedCodec {
+    /// Creates a codec accepting payloads up to `DEFAULT_MAX_FRAME_LENGTH` bytes
+    ///
+    /// # Returns
+    ///
+    /// A new `LengthPrefixedCodec`
+    pub fn new() -> Self {
+        LengthPrefixedCodec::default()
+    }
+
+    /// Creates a codec accepting payloads up to the given length
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The maximum length of a payload, in bytes, excluding its prefix
+    ///
+    /// # Returns
+    ///
+    /// A new `LengthPrefixedCodec`
+    pub fn with_max_length(max_length: usize) -> Self {
+        LengthPrefixedCodec { max_length }
+    }
+
+    /// Gets the maximum length of a payload
+    ///
+    /// # Returns
+    ///
+    /// The maximum length of a payload, in bytes
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Creates a reader of length-prefixed messages
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read from
+    ///
+    /// # Returns
+    ///
+    /// A new `LengthPrefixedReader`
+    pub fn reader<R: Read>(self, reader: R) -> LengthPrefixedReader<R> {
+        LengthPrefixedReader {
+            codec: self,
+            reader,
+            payload: Vec::new(),
+        }
+    }
+
+    /// Creates a writer of length-prefixed messages
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    ///
+    /// # Returns
+    ///
+    /// A new `LengthPrefixedWriter`
+    pub fn writer<W: Write>(self, writer: W) -> LengthPrefixedWriter<W> {
+        LengthPrefixedWriter {
+            codec: self,
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Checks that a payload fits the maximum length and the `u32` prefix
+    pub(super) fn check_length(&self, length: usize) -> Result<u32, FrameError> {
+        match u32::try_from(length) {
+            Ok(prefix) if length <= self.max_length => Ok(prefix),
+            _ => Err(FrameError::TooLarge {
+                limit: self.max_length,
+            }),
+        }
+    }
+}
+
+/// Reads length-prefixed messages from a stream
+///
+/// Also an iterator over the messages, ending at the end of the stream.
+#[derive(Debug)]
+pub struct LengthPrefixedReader<R> {
+    codec: LengthPrefixedCodec,
+    reader: R,
+    payload: Vec<u8>,
+}
+
+impl<R: Read> LengthPrefixedReader<R> {
+    /// Reads the next message
+    ///
+    /// A payload that is too long or that fails to parse is consumed and
+    /// reported as an error; the following messages can still be read.
+    ///
+    /// # Returns
+    ///
+    /// The next message, None at the end of the stream, or the error encountered
+    pub fn read_message(&mut self) -> Result<Option<Message>, FrameError> {
+        let mut prefix = [0; PREFIX_LENGTH];
+        if !self.read_prefix(&mut prefix)? {
+            return Ok(None);
+        }
+        let length = u32::from_be_bytes(prefix) as usize;
+
+        if length > self.codec.max_length {
+            let skipped = io::copy(&mut (&mut self.reader).take(length as u64), &mut io::sink())?;
+            if skipped < length as u64 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            return Err(FrameError::TooLarge {
+                limit: self.codec.max_length,
+            });
+        }
+
+        self.payload.clear();
+        self.payload.resize(length, 0);
+        self.reader.read_exact(&mut self.payload)?;
+        Ok(Some(parse_payload(&self.payload)?))
+    }
+
+    /// Reads a frame prefix, returning `false` at the end of the stream
+    fn read_prefix(&mut self, prefix: &mut [u8; PREFIX_LENGTH]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < PREFIX_LENGTH {
+            match self.reader.read(&mut prefix[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Gets a reference to the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for LengthPrefixedReader<R> {
+    type Item = Result<Message, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message().transpose()
+    }
+}
+
+/// Writes length-prefixed messages to a stream
+#[derive(Debug)]
+pub struct LengthPrefixedWriter<W> {
+    codec: LengthPrefixedCodec,
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> LengthPrefixedWriter<W> {
+    /// Writes a message with its length prefix and flushes the stream
+    ///
+    /// A payload longer than the maximum length is not written, and is
+    /// reported as `FrameError::TooLarge`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The payload to write, e.g. a `JsonRpc`, a `Message` or a `Vec<JsonRpc>`
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message is written, or the error encountered
+    pub fn write_message<T: Serialize + ?Sized>(&mut self, message: &T) -> Result<(), FrameError> {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&[0; PREFIX_LENGTH]);
+        serde_json::to_writer(&mut self.buffer, message).map_err(io::Error::from)?;
+
+        let length = self.codec.check_length(self.buffer.len() - PREFIX_LENGTH)?;
+        self.buffer[..PREFIX_LENGTH].copy_from_slice(&length.to_be_bytes());
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Gets a reference to the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwraps the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonRpc;
+    use serde_json::json;
+
+    fn frame(payload: &str) -> Vec<u8> {
+        let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(payload.as_bytes());
+        frame
+    }
+
+    #[test]
+    fn reads_pretty_printed_payloads() {
+        let request = JsonRpc::request_with_params(1, "echo", json!({"text": "a\nb"}));
+        let batch = vec![JsonRpc::notification("a"), JsonRpc::request(2, "b")];
+        let mut input = frame(&serde_json::to_string_pretty(&request).unwrap());
+        input.extend(frame(&serde_json::to_string_pretty(&batch).unwrap()));
+
+        let messages: Vec<_> = LengthPrefixedCodec::new()
+            .reader(&input[..])
+            .collect::<Result<_, _>>()
+            .expect("Unable to read messages");
+        assert_eq!(
+            messages,
+            vec![Message::Single(request), Message::from(batch)]
+        );
+    }
+
+    #[test]
+    fn recovers_from_bad_payloads() {
+        let mut input = frame(r#"{"jsonrpc":"2.0","method":"too-long"}"#);
+        input.extend(frame("{"));
+        input.extend(frame(r#"{"jsonrpc":"2.0","method":"a"}"#));
+        input.extend_from_slice(&[0, 0]);
+        let mut reader = LengthPrefixedCodec::with_max_length(30).reader(&input[..]);
+
+        assert!(matches!(
+            reader.read_message(),
+            Err(FrameError::TooLarge { limit: 30 })
+        ));
+        assert!(
+            matches!(reader.read_message(), Err(FrameError::Parse(ref err)) if err.code() == -32700)
+        );
+        assert_eq!(
+            reader.read_message().expect("Unable to read message"),
+            Some(Message::Single(JsonRpc::notification("a")))
+        );
+        assert!(matches!(
+            reader.read_message(),
+            Err(FrameError::Io(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn writes_prefixes() {
+        let mut writer = LengthPrefixedCodec::with_max_length(30).writer(Vec::new());
+        writer
+            .write_message(&JsonRpc::notification("a"))
+            .expect("Unable to write message");
+        assert!(matches!(
+            writer.write_message(&JsonRpc::notification("too-long")),
+            Err(FrameError::TooLarge { limit: 30 })
+        ));
+        assert_eq!(
+            writer.into_inner(),
+            frame(r#"{"jsonrpc":"2.0","method":"a"}"#)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Newline-delimited JSON-RPC framing
+
+use std::io::{self, BufRead, Write};
+
+use serde::Serialize;
+
+use super::{parse_payload, read_line, FrameError, Line, DEFAULT_MAX_FRAME_LENGTH};
+use crate::jsonrpc::Message;
+
+/// Newline-delimited framing: one serialized payload per line
+///
+/// Lines may end with `\n` or `\r\n`, blank lines are skipped, and the last
+/// line of a stream may omit its line ending. Payloads are written compact,
+/// so they never contain a raw newline.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::framing::LineCodec;
+/// use jsonrpc_lite::{JsonRpc, Message};
+///
+/// let mut output = Vec::new();
+/// let mut writer = LineCodec::new().writer(&mut output);
+/// writer.write_message(&JsonRpc::notification("ping")).unwrap();
+/// assert_eq!(output, b"{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n");
+///
+/// let mut reader = LineCodec::new().reader(&output[..]);
+/// let message = reader.read_message().unwrap();
+/// assert_eq!(message, Some(Message::Single(JsonRpc::notification("ping"))));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineCodec {
+    max_length: usize,
+}
+
+impl Default for LineCodec {
+    fn default() -> Self {
+        LineCodec {
+            max_length: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+}
+
+impl LineCodec {
+    /// Creates a codec accepting lines up to `DEFAULT_MAX_FRAME_LENGTH` bytes
+    ///
+    /// # Returns
+    ///
+    /// A new `LineCodec`
+    pub fn new() -> Self {
+        LineCodec::default()
+    }
+
+    /// Creates a codec accepting lines up to the given length
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The maximum length of a line, in bytes, excluding its line ending
+    ///
+    /// # Returns
+    ///
+    /// A new `LineCodec`
+    pub fn with_max_length(max_length: usize) -> Self {
+        LineCodec { max_length }
+    }
+
+    /// Gets the maximum length of a line
+    ///
+    /// # Returns
+    ///
+    /// The maximum length of a line, in bytes
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Creates a reader of newline-delimited messages
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The buffered stream to read from
+    ///
+    /// # Returns
+    ///
+    /// A new `LineReader`
+    pub fn reader<R: BufRead>(self, reader: R) -> LineReader<R> {
+        LineReader {
+            codec: self,
+            reader,
+            line: Vec::new(),
+        }
+    }
+
+    /// Creates a writer of newline-delimited messages
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    ///
+    /// # Returns
+    ///
+    /// A new `LineWriter`
+    pub fn writer<W: Write>(self, writer: W) -> LineWriter<W> {
+        LineWriter {
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Reads newline-delimited messages from a buffered stream
+///
+/// Also an iterator over the messages, ending at the end of the stream.
+#[derive(Debug)]
+pub struct LineReader<R> {
+    codec: LineCodec,
+    reader: R,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> LineReader<R> {
+    /// Reads the next message
+    ///
+    /// A line that is too long or that fails to parse is consumed and reported
+    /// as an error; the following lines can still be read.
+    ///
+    /// # Returns
+    ///
+    /// The next message, None at the end of the stream, or the error encountered
+    pub fn read_message(&mut self) -> Result<Option<Message>, FrameError> {
+        loop {
+            match read_line(&mut self.reader, &mut self.line, self.codec.max_length)? {
+                Line::Eof => return Ok(None),
+                Line::TooLong => {
+                    return Err(FrameError::TooLarge {
+                        limit: self.codec.max_length,
+                    })
+                }
+                Line::Complete => {
+                    let line = self.line.trim_ascii();
+                    if !line.is_empty() {
+                        return Ok(Some(parse_payload(line)?));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets a reference to the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for LineReader<R> {
+    type Item = Result<Message, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message().transpose()
+    }
+}
+
+/// Writes newline-delimited messages to a stream
+#[derive(Debug)]
+pub struct LineWriter<W> {
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> LineWriter<W> {
+    /// Writes a message as one line and flushes the stream
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The payload to write, e.g. a `JsonRpc`, a `Message` or a `Vec<JsonRpc>`
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the line is written, or the error encountered
+    pub fn write_message<T: Serialize + ?Sized>(&mut self, message: &T) -> Result<(), FrameError> {
+        self.buffer.clear();
+        serde_json::to_writer(&mut self.buffer, message).map_err(io::Error::from)?;
+        self.buffer.push(b'\n');
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Gets a reference to the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwraps the underlying stream
+    ///
+    /// # Returns
+    ///
+    /// The underlying stream
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonRpc;
+    use serde_json::json;
+    use std::io::{BufReader, Read};
+
+    /// Hands out its data one byte per read
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_lines() {
+        let input = b"\n{\"jsonrpc\":\"2.0\",\"method\":\"a\"}\r\n  \r\n\
+                      [{\"jsonrpc\":\"2.0\",\"method\":\"b\",\"id\":1}]\n\
+                      {\"jsonrpc\":\"2.0\",\"method\":\"c\"}";
+        let reader = BufReader::with_capacity(4, Trickle(input));
+        let messages: Vec<_> = LineCodec::new()
+            .reader(reader)
+            .collect::<Result<_, _>>()
+            .expect("Unable to read messages");
+        assert_eq!(
+            messages,
+            vec![
+                Message::Single(JsonRpc::notification("a")),
+                Message::from(vec![JsonRpc::request(1, "b")]),
+                Message::Single(JsonRpc::notification("c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovers_from_bad_lines() {
+        let input = b"{\"jsonrpc\":\"2.0\",\"method\":\"too-long\"}\n{\"oops\n\
+                      {\"jsonrpc\":\"2.0\",\"method\":\"a\"}\n";
+        let mut reader = LineCodec::with_max_length(30).reader(&input[..]);
+
+        assert!(matches!(
+            reader.read_message(),
+            Err(FrameError::TooLarge { limit: 30 })
+        ));
+        match reader.read_message() {
+            Err(FrameError::Parse(err)) => assert_eq!(err.code(), -32700),
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+        assert_eq!(
+            reader.read_message().expect("Unable to read message"),
+            Some(Message::Single(JsonRpc::notification("a")))
+        );
+        assert_eq!(reader.read_message().expect("Unable to read EOF"), None);
+    }
+
+    #[test]
+    fn writes_lines() {
+        let mut writer = LineCodec::new().writer(Vec::new());
+        writer
+            .write_message(&JsonRpc::success(1, &json!("a\nb")))
+            .expect("Unable to write message");
+        writer
+            .write_message(&vec![JsonRpc::notification("c")])
+            .expect("Unable to write batch");
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "{\"jsonrpc\":\"2.0\",\"result\":\"a\\nb\",\"id\":1}\n\
+             [{\"jsonrpc\":\"2.0\",\"method\":\"c\"}]\n"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Framing of JSON-RPC messages over byte streams
+//!
+//! This module provides codecs that delimit serialized JSON-RPC payloads on a
+//! byte stream, with blocking readers and writers over `std::io`:
+//!
+//! - `LineCodec`: newline-delimited JSON, one payload per line
+//! - `HeaderCodec`: `Content-Length` header framing, as used by the Language
+//!   Server Protocol and the Debug Adapter Protocol
+//! - `LengthPrefixedCodec`: a big-endian `u32` length before each payload
+//!
+//! Readers yield [`Message`](crate::Message) values, so a single message and
+//! a batch are read the same way. A payload that fails to parse is reported as
+//! `FrameError::Parse` without desynchronizing the stream, so reading can go on.
+//!
+//! With the `tokio` feature, the codecs also implement `tokio_util::codec`'s
+//! `Decoder` and `Encoder`, for use with `Framed` over any async stream. The
+//! newline-delimited decoder keeps track of how far it has searched its buffer,
+//! so it is a separate `LineDecoder`, created by `LineCodec::decoder`.
+
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::ParseError;
+
+#[cfg(feature = "tokio")]
+mod codec;
+mod header;
+mod length;
+mod line;
+
+#[cfg(feature = "tokio")]
+pub use self::codec::LineDecoder;
+pub use self::header::{HeaderCodec, HeaderReader, HeaderWriter};
+pub use self::length::{LengthPrefixedCodec, LengthPrefixedReader, LengthPrefixedWriter};
+pub use self::line::{LineCodec, LineReader, LineWriter};
+
+/// The largest frame accepted by default, in bytes
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A framing codec selected at run time, e.g. by `server::serve`
+///
+/// More framings may be added, so matches on it need a wildcard arm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Framing {
+    /// Newline-delimited framing
+    Line(LineCodec),
+    /// `Content-Length` header framing
+    Header(HeaderCodec),
+    /// Length-prefixed framing
+    LengthPrefixed(LengthPrefixedCodec),
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::Header(HeaderCodec::new())
+    }
+}
+
+impl From<LineCodec> for Framing {
+    fn from(codec: LineCodec) -> Self {
+        Framing::Line(codec)
+    }
+}
+
+impl From<HeaderCodec> for Framing {
+    fn from(codec: HeaderCodec) -> Self {
+        Framing::Header(codec)
+    }
+}
+
+impl From<LengthPrefixedCodec> for Framing {
+    fn from(codec: LengthPrefixedCodec) -> Self {
+        Framing::LengthPrefixed(codec)
+    }
+}
+
+/// Errors reported while reading or writing framed messages
+#[derive(Debug)]
+pub enum FrameError {
+    /// The underlying stream failed
+    Io(io::Error),
+    /// A frame was read, but its payload is not a valid JSON-RPC payload
+    Parse(ParseError),
+    /// A frame exceeded the maximum length and was discarded
+    TooLarge {
+        /// The maximum frame length, in bytes
+        limit: usize,
+    },
+    /// A frame header is malformed, so the stream cannot be read any further
+    InvalidHeader(String),
+}
+
+impl FrameError {
+    /// Checks whether the stream can still be read after this error
+    ///
+    /// # Returns
+    ///
+    /// `true` if the offending frame was skipped and the next one can be read
+    pub fn is_recoverable(&self) -> bool {
+        matches!(*self, FrameError::Parse(_) | FrameError::TooLarge { .. })
+    }
+}
+
+impl fmt::Display for FrameError {
+    /// Formats the frame error as a string
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter to write to
+    ///
+    /// # Returns
+    ///
+    /// A `fmt::Result` indicating success or failure of the formatting operation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameError::Io(ref err) => write!(f, "I/O error: {}", err),
+            FrameError::Parse(ref err) => write!(f, "invalid payload: {}", err),
+            FrameError::TooLarge { limit } => {
+                write!(f, "frame exceeds the maximum length of {} bytes", limit)
+            }
+            FrameError::InvalidHeader(ref reason) => write!(f, "invalid frame header: {}", reason),
+        }
+    }
+}
+
+impl error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            FrameError::Io(ref err) => Some(err),
+            FrameError::Parse(ref err) => Some(err),
+            FrameError::TooLarge { .. } | FrameError::InvalidHeader(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for FrameError {
+    fn from(err: io::Error) -> Self {
+        FrameError::Io(err)
+    }
+}
+
+impl From<ParseError> for FrameError {
+    fn from(err: ParseError) -> Self {
+        FrameError::Parse(err)
+    }
+}
+
+/// Parses a frame payload into a single message or a batch
+pub(crate) fn parse_payload(payload: &[u8]) -> Result<crate::Message, ParseError> {
+    let input = std::str::from_utf8(payload).map_err(|err| {
+        ParseError::new(crate::Error::parse_error().with_data(err.to_string()), None)
+    })?;
+    crate::JsonRpc::parse_message(input)
+}
+
+/// The outcome of reading one line with `read_line`
+enum Line {
+    /// The end of the stream was reached before any byte of the line
+    Eof,
+    /// A line was read, without its line ending
+    Complete,
+    /// A line longer than the limit was read and discarded
+    TooLong,
+}
+
+/// Reads one line terminated by `\n` or `\r\n`, or by the end of the stream
+///
+/// The line is stored in `line` without its line ending. A line longer than
+/// `limit` is consumed through its line ending but not stored.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>, limit: usize) -> io::Result<Line> {
+    line.clear();
+    let mut read_any = false;
+    let mut too_long = false;
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if available.is_empty() {
+            break;
+        }
+        read_any = true;
+
+        let newline = available.iter().position(|&b| b == b'\n');
+        let end = newline.unwrap_or(available.len());
+        if !too_long {
+            line.extend_from_slice(&available[..end]);
+            // Leave room for a `\r` preceding the `\n`
+            if line.len() > limit.saturating_add(1) {
+                too_long = true;
+                line.clear();
+            }
+        }
+        reader.consume(newline.map_or(end, |end| end + 1));
+        if newline.is_some() {
+            break;
+        }
+    }
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    if too_long || line.len() > limit {
+        line.clear();
+        Ok(Line::TooLong)
+    } else if read_any {
+        Ok(Line::Complete)
+    } else {
+        Ok(Line::Eof)
+    }
+}
@@ -0,0 +1,354 @@
+//! JSON-RPC 2.0 over HTTP POST
+//!
+//! This module translates between the `http` crate's request and response
+//! types and JSON-RPC messages, so it works with any server or client built
+//! on them, such as hyper or axum. Bodies are handled as complete byte
+//! buffers: collect a streaming body before handing it over.
+//!
+//! On the server side, `handle_request` and, with the `async` feature,
+//! `handle_request_async` dispatch a POST request to a router:
+//!
+//! - methods other than POST are answered with 405 Method Not Allowed
+//! - bodies that are not `application/json` are answered with 415 Unsupported Media Type
+//! - payloads that fail to parse are answered with 200 OK and the matching
+//!   parse error or invalid request error response
+//! - payloads made of notifications only are answered with 204 No Content
+//! - other payloads, batches included, are answered with 200 OK and their responses
+//!
+//! On the client side, `to_request` builds the POST request for a message and
+//! `from_response` reads the response payload back.
+
+use std::error;
+use std::fmt;
+
+use ::http::header::{HeaderValue, ACCEPT, ALLOW, CONTENT_TYPE};
+use ::http::{Method, Request, Response, StatusCode, Uri};
+
+use crate::error::ParseError;
+use crate::framing::parse_payload;
+use crate::jsonrpc::{encode_message, Message};
+#[cfg(feature = "async")]
+use crate::router::AsyncRouter;
+use crate::router::Router;
+
+/// The media type of JSON-RPC payloads
+pub const CONTENT_TYPE_JSON: &str = "application/json";
+
+/// Dispatches an HTTP request holding a JSON-RPC payload to a router
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching the payload
+/// * `request` - The HTTP request, with its complete body
+///
+/// # Returns
+///
+/// The HTTP response to send back
+///
+/// # Examples
+///
+/// ```
+/// use http::{Request, StatusCode};
+/// use jsonrpc_lite::http::handle_request;
+/// use jsonrpc_lite::{Params, Router};
+///
+/// let mut router = Router::new();
+/// router.add_method("ping", |_: Params| Ok("pong"));
+///
+/// let request = Request::post("/rpc")
+///     .header("Content-Type", "application/json")
+///     .body(r#"{"jsonrpc":"2.0","method":"ping","id":1}"#)
+///     .unwrap();
+/// let response = handle_request(&router, request);
+/// assert_eq!(response.status(), StatusCode::OK);
+/// assert_eq!(response.body(), br#"{"jsonrpc":"2.0","result":"pong","id":1}"#);
+/// ```
+pub fn handle_request<B: AsRef<[u8]>>(router: &Router, request: Request<B>) -> Response<Vec<u8>> {
+    if let Some(response) = reject(&request) {
+        return response;
+    }
+    match parse_payload(request.body().as_ref()) {
+        Ok(message) => respond(router.handle_message(message)),
+        Err(err) => respond(Some(Message::Single(err.into_response()))),
+    }
+}
+
+/// Dispatches an HTTP request holding a JSON-RPC payload to an asynchronous router
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching the payload
+/// * `request` - The HTTP request, with its complete body
+///
+/// # Returns
+///
+/// The HTTP response to send back
+#[cfg(feature = "async")]
+pub async fn handle_request_async<B: AsRef<[u8]>>(
+    router: &AsyncRouter,
+    request: Request<B>,
+) -> Response<Vec<u8>> {
+    if let Some(response) = reject(&request) {
+        return response;
+    }
+    match parse_payload(request.body().as_ref()) {
+        Ok(message) => respond(router.handle_message(message).await),
+        Err(err) => respond(Some(Message::Single(err.into_response()))),
+    }
+}
+
+/// Checks the method and content type of an HTTP request
+///
+/// Returns the HTTP response to send back instead of dispatching, if any.
+fn reject<B>(request: &Request<B>) -> Option<Response<Vec<u8>>> {
+    if request.method() != Method::POST {
+        let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+        response
+            .headers_mut()
+            .insert(ALLOW, HeaderValue::from_static("POST"));
+        Some(response)
+    } else if !is_json(request.headers().get(CONTENT_TYPE)) {
+        Some(status(StatusCode::UNSUPPORTED_MEDIA_TYPE))
+    } else {
+        None
+    }
+}
+
+/// Wraps a response payload into an HTTP response
+fn respond(message: Option<Message>) -> Response<Vec<u8>> {
+    let message = match message {
+        Some(message) => message,
+        None => return status(StatusCode::NO_CONTENT),
+    };
+    let mut response = Response::new(encode_message(&message).into_bytes());
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE_JSON));
+    response
+}
+
+/// Creates an HTTP response with an empty body
+fn status(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
+
+/// Checks whether a `Content-Type` header declares JSON
+fn is_json(content_type: Option<&HeaderValue>) -> bool {
+    content_type
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(CONTENT_TYPE_JSON))
+}
+
+/// Builds the HTTP POST request carrying a JSON-RPC payload
+///
+/// # Arguments
+///
+/// * `uri` - The URI of the JSON-RPC endpoint
+/// * `message` - The payload to send, e.g. a `JsonRpc` or a `Vec<JsonRpc>`
+///
+/// # Returns
+///
+/// The HTTP request, or the error encountered building it, e.g. for an invalid URI
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::http::to_request;
+/// use jsonrpc_lite::JsonRpc;
+///
+/// let request = to_request("http://localhost:8080/rpc", JsonRpc::request(1, "ping")).unwrap();
+/// assert_eq!(request.method(), "POST");
+/// assert_eq!(request.headers()["Content-Type"], "application/json");
+/// ```
+pub fn to_request<U, M>(uri: U, message: M) -> ::http::Result<Request<Vec<u8>>>
+where
+    Uri: TryFrom<U>,
+    <Uri as TryFrom<U>>::Error: Into<::http::Error>,
+    M: Into<Message>,
+{
+    let body = encode_message(&message.into()).into_bytes();
+    Request::post(uri)
+        .header(CONTENT_TYPE, CONTENT_TYPE_JSON)
+        .header(ACCEPT, CONTENT_TYPE_JSON)
+        .body(body)
+}
+
+/// Reads the JSON-RPC payload of an HTTP response
+///
+/// # Arguments
+///
+/// * `response` - The HTTP response, with its complete body
+///
+/// # Returns
+///
+/// The response payload, None for a 204 No Content response, or the error encountered
+pub fn from_response<B: AsRef<[u8]>>(response: Response<B>) -> Result<Option<Message>, HttpError> {
+    if response.status() == StatusCode::NO_CONTENT {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(HttpError::Status(response.status()));
+    }
+    if !is_json(response.headers().get(CONTENT_TYPE)) {
+        return Err(HttpError::ContentType);
+    }
+    Ok(Some(parse_payload(response.body().as_ref())?))
+}
+
+/// Errors reported while reading the HTTP response to a JSON-RPC payload
+#[derive(Debug)]
+pub enum HttpError {
+    /// The server answered with an unsuccessful status
+    Status(StatusCode),
+    /// The response body is not declared as `application/json`
+    ContentType,
+    /// The response body is not a valid JSON-RPC payload
+    Parse(ParseError),
+}
+
+impl fmt::Display for HttpError {
+    /// Formats the HTTP error as a string
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter to write to
+    ///
+    /// # Returns
+    ///
+    /// A `fmt::Result` indicating success or failure of the formatting operation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HttpError::Status(status) => write!(f, "unexpected HTTP status {}", status),
+            HttpError::ContentType => write!(f, "response is not {}", CONTENT_TYPE_JSON),
+            HttpError::Parse(ref err) => write!(f, "invalid response payload: {}", err),
+        }
+    }
+}
+
+impl error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            HttpError::Parse(ref err) => Some(err),
+            HttpError::Status(_) | HttpError::ContentType => None,
+        }
+    }
+}
+
+impl From<ParseError> for HttpError {
+    fn from(err: ParseError) -> Self {
+        HttpError::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error as RpcError, JsonRpc, Params};
+    use serde_json::{json, Value};
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router.add_method("echo", |params: Params| Ok(Value::from(params)));
+        router
+    }
+
+    fn post(body: &str) -> Request<&str> {
+        Request::post("/rpc")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(body)
+            .unwrap()
+    }
+
+    fn body(response: &Response<Vec<u8>>) -> Value {
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let get = Request::get("/rpc").body("").unwrap();
+        let response = handle_request(&router(), get);
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "POST");
+
+        let text = Request::post("/rpc")
+            .header(CONTENT_TYPE, "text/plain")
+            .body("{}")
+            .unwrap();
+        let response = handle_request(&router(), text);
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = handle_request(&router(), post("{"));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(&response)["error"]["code"], json!(-32700));
+
+        let response = handle_request(&router(), post("[]"));
+        assert_eq!(body(&response)["error"]["code"], json!(-32600));
+    }
+
+    #[test]
+    fn answers_requests() {
+        let response = handle_request(
+            &router(),
+            post(r#"{"jsonrpc":"2.0","method":"echo","params":[1],"id":1}"#),
+        );
+        assert_eq!(response.headers()[CONTENT_TYPE], CONTENT_TYPE_JSON);
+        assert_eq!(
+            body(&response),
+            json!({"jsonrpc": "2.0", "result": [1], "id": 1})
+        );
+
+        let response = handle_request(
+            &router(),
+            post(r#"[{"jsonrpc":"2.0","method":"echo"},{"jsonrpc":"2.0","method":"echo"}]"#),
+        );
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.body().is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn dispatches_async() {
+        let response = futures::executor::block_on(handle_request_async(
+            &AsyncRouter::new(),
+            post(r#"[{"jsonrpc":"2.0","method":"echo"},{"jsonrpc":"2.0","method":"echo","id":2}]"#),
+        ));
+        assert_eq!(
+            body(&response),
+            json!([{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 2}])
+        );
+    }
+
+    #[test]
+    fn client_round_trip() {
+        let batch = vec![
+            JsonRpc::request_with_params(1, "echo", json!({"a": 1})),
+            JsonRpc::request(2, "missing"),
+        ];
+        let request = to_request("http://localhost/rpc", batch).unwrap();
+        assert_eq!(request.headers()[ACCEPT], CONTENT_TYPE_JSON);
+
+        let response = from_response(handle_request(&router(), request)).unwrap();
+        assert_eq!(
+            response,
+            Some(Message::from(vec![
+                JsonRpc::success(1, &json!({"a": 1})),
+                JsonRpc::error(2, RpcError::method_not_found()),
+            ]))
+        );
+
+        let request = to_request("http://localhost/rpc", JsonRpc::notification("echo")).unwrap();
+        assert_eq!(
+            from_response(handle_request(&router(), request)).unwrap(),
+            None
+        );
+
+        let get = Request::get("/rpc").body(Vec::new()).unwrap();
+        assert!(matches!(
+            from_response(handle_request(&router(), get)),
+            Err(HttpError::Status(StatusCode::METHOD_NOT_ALLOWED))
+        ));
+    }
+}
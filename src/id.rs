@@ -0,0 +1,332 @@
+//! JSON-RPC 2.0 request id generation
+//!
+//! This module provides the `IdGenerator` trait, used by `Client` to allocate
+//! request ids, along with built-in strategies:
+//!
+//! - `SequentialIds`: a monotonic counter, unique within a process
+//! - `RandomIds`: random numbers, unique across processes with high probability
+//! - `UuidIds`: random UUIDv4 strings
+//! - `PrefixedIds`: a counter behind a fixed string prefix, e.g. one per process
+//!
+//! The random strategies, `RandomIds`, `UuidIds` and `PrefixedIds::random`,
+//! need the `rand-ids` feature.
+//!
+//! Sharing one generator between successive connections, e.g. through an
+//! `Arc`, keeps ids unique across reconnects.
+
+use std::fmt;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::jsonrpc::{Id, JsonRpc, Params};
+
+/// Allocates ids for outgoing requests
+pub trait IdGenerator: Send + Sync {
+    /// Allocates the next id
+    ///
+    /// # Returns
+    ///
+    /// An id not returned before by this generator
+    fn next_id(&self) -> Id;
+
+    /// Creates a request object without params, with the next id
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    ///
+    /// # Returns
+    ///
+    /// A new JsonRpc::Request variant
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::id::{IdGenerator, SequentialIds};
+    /// use jsonrpc_lite::JsonRpc;
+    ///
+    /// let ids = SequentialIds::starting_at(7);
+    /// assert_eq!(ids.request("ping"), JsonRpc::request(7, "ping"));
+    /// ```
+    fn request(&self, method: &str) -> JsonRpc {
+        JsonRpc::request(self.next_id(), method)
+    }
+
+    /// Creates a request object with params, with the next id
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    /// * `params` - The parameters to pass to the method
+    ///
+    /// # Returns
+    ///
+    /// A new JsonRpc::Request variant with parameters
+    fn request_with_params<P: Into<Params>>(&self, method: &str, params: P) -> JsonRpc
+    where
+        Self: Sized,
+    {
+        JsonRpc::request_with_params(self.next_id(), method, params)
+    }
+}
+
+impl<G: IdGenerator + ?Sized> IdGenerator for Arc<G> {
+    fn next_id(&self) -> Id {
+        (**self).next_id()
+    }
+}
+
+impl<G: IdGenerator + ?Sized> IdGenerator for Box<G> {
+    fn next_id(&self) -> Id {
+        (**self).next_id()
+    }
+}
+
+/// Numeric ids from a monotonic counter
+#[derive(Debug, Default)]
+pub struct SequentialIds {
+    next: AtomicI64,
+}
+
+impl SequentialIds {
+    /// Creates a counter starting at 0
+    ///
+    /// # Returns
+    ///
+    /// A new `SequentialIds` generator
+    pub fn new() -> Self {
+        SequentialIds::default()
+    }
+
+    /// Creates a counter starting at the given value
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The first id to return
+    ///
+    /// # Returns
+    ///
+    /// A new `SequentialIds` generator
+    pub fn starting_at(start: i64) -> Self {
+        SequentialIds {
+            next: AtomicI64::new(start),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> Id {
+        Id::from(self.next.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Random numeric ids
+///
+/// Ids are drawn from the operating system's random number generator and kept
+/// below 2^53, so that peers reading numbers as doubles, such as JavaScript,
+/// echo them exactly.
+///
+/// # Panics
+///
+/// Generating an id panics if the operating system's random number generator fails.
+#[cfg(feature = "rand-ids")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomIds;
+
+#[cfg(feature = "rand-ids")]
+impl RandomIds {
+    /// Creates a random id generator
+    ///
+    /// # Returns
+    ///
+    /// A new `RandomIds` generator
+    pub fn new() -> Self {
+        RandomIds
+    }
+}
+
+#[cfg(feature = "rand-ids")]
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> Id {
+        let bytes: [u8; 8] = random_bytes();
+        Id::from(u64::from_le_bytes(bytes) >> 11)
+    }
+}
+
+/// Random UUID version 4 string ids, e.g. "936da01f-9abd-4d9d-80c7-02af85c822a8"
+///
+/// # Panics
+///
+/// Generating an id panics if the operating system's random number generator fails.
+#[cfg(feature = "rand-ids")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UuidIds;
+
+#[cfg(feature = "rand-ids")]
+impl UuidIds {
+    /// Creates a UUIDv4 id generator
+    ///
+    /// # Returns
+    ///
+    /// A new `UuidIds` generator
+    pub fn new() -> Self {
+        UuidIds
+    }
+}
+
+#[cfg(feature = "rand-ids")]
+impl IdGenerator for UuidIds {
+    fn next_id(&self) -> Id {
+        let mut bytes: [u8; 16] = random_bytes();
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let mut uuid = String::with_capacity(36);
+        for (i, byte) in bytes.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                uuid.push('-');
+            }
+            uuid.push(char::from(HEX[usize::from(byte >> 4)]));
+            uuid.push(char::from(HEX[usize::from(byte & 0x0f)]));
+        }
+        Id::Str(uuid)
+    }
+}
+
+/// String ids made of a fixed prefix and a counter, e.g. "worker-3-0", "worker-3-1"
+pub struct PrefixedIds {
+    prefix: String,
+    next: AtomicU64,
+}
+
+impl PrefixedIds {
+    /// Creates a counter behind the given prefix, starting at 0
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix of every id, including any separator
+    ///
+    /// # Returns
+    ///
+    /// A new `PrefixedIds` generator
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::id::{IdGenerator, PrefixedIds};
+    /// use jsonrpc_lite::Id;
+    ///
+    /// let ids = PrefixedIds::new("worker-3-");
+    /// assert_eq!(ids.next_id(), Id::Str(String::from("worker-3-0")));
+    /// assert_eq!(ids.next_id(), Id::Str(String::from("worker-3-1")));
+    /// ```
+    pub fn new<S: Into<String>>(prefix: S) -> Self {
+        PrefixedIds {
+            prefix: prefix.into(),
+            next: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a counter behind a random prefix, distinct for every generator
+    ///
+    /// # Returns
+    ///
+    /// A new `PrefixedIds` generator
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operating system's random number generator fails.
+    #[cfg(feature = "rand-ids")]
+    pub fn random() -> Self {
+        let bytes: [u8; 8] = random_bytes();
+        Self::new(format!("{:016x}-", u64::from_le_bytes(bytes)))
+    }
+
+    /// Gets the prefix of the ids
+    ///
+    /// # Returns
+    ///
+    /// The prefix string
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl IdGenerator for PrefixedIds {
+    fn next_id(&self) -> Id {
+        let n = self.next.fetch_add(1, Ordering::Relaxed);
+        Id::Str(format!("{}{}", self.prefix, n))
+    }
+}
+
+impl fmt::Debug for PrefixedIds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PrefixedIds")
+            .field("prefix", &self.prefix)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+/// Fills an array from the operating system's random number generator
+#[cfg(feature = "rand-ids")]
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    getrandom::fill(&mut bytes).expect("operating system random number generator failed");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "rand-ids")]
+    use std::collections::HashSet;
+
+    #[test]
+    fn sequential() {
+        let ids = Arc::new(SequentialIds::new());
+        let shared = ids.clone();
+        assert_eq!(ids.next_id(), Id::from(0));
+        assert_eq!(shared.next_id(), Id::from(1));
+        assert_eq!(
+            shared.request_with_params("a", vec![]),
+            JsonRpc::request_with_params(2, "a", vec![])
+        );
+    }
+
+    #[cfg(feature = "rand-ids")]
+    #[test]
+    fn random_ids_are_distinct() {
+        let generators: Vec<Box<dyn IdGenerator>> = vec![
+            Box::new(RandomIds::new()),
+            Box::new(UuidIds::new()),
+            Box::new(PrefixedIds::random()),
+        ];
+        for ids in generators {
+            let unique: HashSet<Id> = (0..1000).map(|_| ids.next_id()).collect();
+            assert_eq!(unique.len(), 1000);
+        }
+
+        match RandomIds::new().next_id() {
+            Id::Num(n) => assert!(n.as_u64().is_some_and(|n| n < 1 << 53)),
+            id => panic!("Expected a numeric id, got {:?}", id),
+        }
+    }
+
+    #[cfg(feature = "rand-ids")]
+    #[test]
+    fn uuid_format() {
+        let id = match UuidIds::new().next_id() {
+            Id::Str(id) => id,
+            id => panic!("Expected a string id, got {:?}", id),
+        };
+        let groups: Vec<_> = id.split('-').map(str::len).collect();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&id[14..15], "4");
+        assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"));
+        assert!(id
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f' | b'-')));
+    }
+}
@@ -7,10 +7,18 @@
 //! The main type is `JsonRpc` which represents all possible JSON-RPC message types.
 //! Helper methods are provided for creating and parsing JSON-RPC messages.
 
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Read;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess,
+    Visitor,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::{Map, Result as SerdeResult, Value};
+use serde_json::{Map, Number, Result as SerdeResult, Value};
 
-use crate::Error as RpcError;
+use crate::{Error as RpcError, ParseError};
 
 /// An identifier established by the Client that MUST contain a String, Number,
 /// or NULL value if included. If it is not included it is assumed to be a notification.
@@ -18,11 +26,17 @@ use crate::Error as RpcError;
 ///
 /// As per the JSON-RPC 2.0 specification, this identifier is used to correlate
 /// requests with their corresponding responses.
-#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
-#[serde(untagged)]
+///
+/// Numeric identifiers are serde_json Numbers, so signed, unsigned and
+/// fractional ids are all accepted. Without the `arbitrary_precision` feature
+/// they are held as an i64, u64 or f64: `1e2` is echoed as `100.0`, `-0` as
+/// `-0.0`, and integers beyond 64 bits lose precision. Responses echo the id
+/// exactly as sent only with `arbitrary_precision`, apart from serde_json
+/// writing the sign of an exponent, as in `1e+2`.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub enum Id {
     /// Numeric identifier
-    Num(i64),
+    Num(Number),
     /// String identifier
     Str(String),
     /// Null identifier (represented as unit type in Rust)
@@ -44,6 +58,59 @@ impl From<()> for Id {
     }
 }
 
+impl Id {
+    /// Checks whether this is a numeric identifier with a fractional part
+    ///
+    /// The specification says numbers SHOULD NOT contain fractional parts, so
+    /// servers may want to log a warning when a peer sends one.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the identifier is a number that is not an integer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::JsonRpc;
+    ///
+    /// let request = JsonRpc::parse(r#"{"jsonrpc":"2.0","method":"a","id":1.5}"#).unwrap();
+    /// assert!(request.get_id().unwrap().is_fractional());
+    /// ```
+    pub fn is_fractional(&self) -> bool {
+        match *self {
+            Id::Num(ref n) => n.is_f64(),
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for Id {
+    /// Serializes numeric identifiers exactly as they were read, so that a
+    /// response echoes the number the caller sent
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Id::Num(ref n) => n.serialize(serializer),
+            Id::Str(ref s) => serializer.serialize_str(s),
+            Id::None(()) => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl From<i32> for Id {
+    /// Converts an i32 to Id::Num
+    ///
+    /// # Arguments
+    ///
+    /// * `val` - The i32 value to convert
+    ///
+    /// # Returns
+    ///
+    /// A new Id::Num variant containing the provided value
+    fn from(val: i32) -> Self {
+        Id::Num(val.into())
+    }
+}
+
 impl From<i64> for Id {
     /// Converts an i64 to Id::Num
     ///
@@ -55,6 +122,36 @@ impl From<i64> for Id {
     ///
     /// A new Id::Num variant containing the provided value
     fn from(val: i64) -> Self {
+        Id::Num(val.into())
+    }
+}
+
+impl From<u64> for Id {
+    /// Converts a u64 to Id::Num
+    ///
+    /// # Arguments
+    ///
+    /// * `val` - The u64 value to convert
+    ///
+    /// # Returns
+    ///
+    /// A new Id::Num variant containing the provided value
+    fn from(val: u64) -> Self {
+        Id::Num(val.into())
+    }
+}
+
+impl From<Number> for Id {
+    /// Converts a serde_json Number to Id::Num
+    ///
+    /// # Arguments
+    ///
+    /// * `val` - The Number to convert
+    ///
+    /// # Returns
+    ///
+    /// A new Id::Num variant containing the provided value
+    fn from(val: Number) -> Self {
         Id::Num(val)
     }
 }
@@ -80,7 +177,7 @@ impl From<String> for Id {
 ///
 /// Parameters can be provided as either an ordered array or a named map,
 /// as per the JSON-RPC 2.0 specification.
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize)]
 #[serde(untagged)]
 pub enum Params {
     /// Parameters as an ordered array of values
@@ -91,6 +188,116 @@ pub enum Params {
     None(()),
 }
 
+impl Params {
+    /// Deserializes the parameters into a typed value
+    ///
+    /// Named parameters deserialize into a struct or map, and positional
+    /// parameters into a tuple, a sequence, or a struct by field order.
+    /// Omitted parameters deserialize from `null`, e.g. into `()` or an `Option`.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the typed parameters, or an invalid params
+    /// error (-32602) whose `data` holds an error `message`, and with the
+    /// `param-paths` feature the `path` of the offending parameter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::Params;
+    /// use serde::Deserialize;
+    /// use serde_json::json;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Subtract {
+    ///     minuend: i64,
+    ///     subtrahend: i64,
+    /// }
+    ///
+    /// let named = Params::from(json!({"subtrahend": 23, "minuend": 42}));
+    /// let params: Subtract = named.parse().unwrap();
+    /// assert_eq!(params.minuend - params.subtrahend, 19);
+    ///
+    /// let positional = Params::from(json!([42, 23]));
+    /// let (minuend, subtrahend): (i64, i64) = positional.parse().unwrap();
+    /// assert_eq!(minuend - subtrahend, 19);
+    ///
+    /// let err = Params::from(json!({"minuend": 42, "subtrahend": "x"}))
+    ///     .parse::<Subtract>()
+    ///     .unwrap_err();
+    /// assert_eq!(err.code, -32602);
+    /// # #[cfg(feature = "param-paths")]
+    /// assert_eq!(err.data.unwrap()["path"], "subtrahend");
+    /// ```
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, RpcError> {
+        #[cfg(feature = "param-paths")]
+        let parsed = serde_path_to_error::deserialize(ParamsRef(self)).map_err(|err| {
+            serde_json::json!({
+                "path": err.path().to_string(),
+                "message": err.inner().to_string(),
+            })
+        });
+        #[cfg(not(feature = "param-paths"))]
+        let parsed = T::deserialize(ParamsRef(self))
+            .map_err(|err| serde_json::json!({ "message": err.to_string() }));
+        parsed.map_err(|data| RpcError::invalid_params().with_data(data))
+    }
+}
+
+/// Deserializes borrowed parameters the way serde_json deserializes a `&Value`,
+/// without cloning them into one
+struct ParamsRef<'a>(&'a Params);
+
+impl<'de> Deserializer<'de> for ParamsRef<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match *self.0 {
+            Params::Array(ref values) => {
+                let mut seq = de::value::SeqDeserializer::new(values.iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Params::Map(ref map) => map.deserialize_any(visitor),
+            Params::None(()) => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match *self.0 {
+            Params::None(()) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        match *self.0 {
+            Params::Map(ref map) => map.deserialize_enum(name, variants, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
 impl From<Value> for Params {
     /// Converts a serde_json::Value to Params
     ///
@@ -166,20 +373,62 @@ impl From<Params> for Value {
 /// A request object represents a call to a method on the server.
 /// It contains a method name, optional parameters, and an identifier
 /// that will be used to match the response to this request.
+///
+/// The parameters default to [`Params`], but any serde type can be used to
+/// serialize and deserialize them directly.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Request {
+pub struct Request<P = Params> {
     /// JSON-RPC protocol version (always "2.0")
-    jsonrpc: String,
+    pub(crate) jsonrpc: String,
     /// Name of the method to be invoked
     pub method: String,
     /// Parameters to be used during the invocation of the method
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub params: Option<Params>,
+    pub params: Option<P>,
     /// Client-established identifier for this request
     pub id: Id,
 }
 
-impl Request {
+impl<P> Request<P> {
+    /// Creates a JSON-RPC 2.0 request object
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier for the request
+    /// * `method` - The name of the method to call
+    /// * `params` - The parameters to pass to the method, if any
+    ///
+    /// # Returns
+    ///
+    /// A new Request
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::Request;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Add {
+    ///     a: i32,
+    ///     b: i32,
+    /// }
+    ///
+    /// let request = Request::new(1, "add", Some(Add { a: 1, b: 2 }));
+    /// assert_eq!(
+    ///     serde_json::to_string(&request).unwrap(),
+    ///     r#"{"jsonrpc":"2.0","method":"add","params":{"a":1,"b":2},"id":1}"#
+    /// );
+    /// ```
+    pub fn new<I: Into<Id>>(id: I, method: &str, params: Option<P>) -> Self {
+        Request {
+            jsonrpc: String::from("2.0"),
+            method: String::from(method),
+            params,
+            id: id.into(),
+        }
+    }
+
     /// Gets the JSON-RPC protocol version
     ///
     /// # Returns
@@ -194,18 +443,38 @@ impl Request {
 ///
 /// A notification is similar to a request but does not require a response.
 /// It contains a method name and optional parameters, but no identifier.
+///
+/// The parameters default to [`Params`], but any serde type can be used.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Notification {
+pub struct Notification<P = Params> {
     /// JSON-RPC protocol version (always "2.0")
-    jsonrpc: String,
+    pub(crate) jsonrpc: String,
     /// Name of the method to be invoked
     pub method: String,
     /// Parameters to be used during the invocation of the method
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub params: Option<Params>,
+    pub params: Option<P>,
 }
 
-impl Notification {
+impl<P> Notification<P> {
+    /// Creates a JSON-RPC 2.0 notification object
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    /// * `params` - The parameters to pass to the method, if any
+    ///
+    /// # Returns
+    ///
+    /// A new Notification
+    pub fn new(method: &str, params: Option<P>) -> Self {
+        Notification {
+            jsonrpc: String::from("2.0"),
+            method: String::from(method),
+            params,
+        }
+    }
+
     /// Gets the JSON-RPC protocol version
     ///
     /// # Returns
@@ -220,17 +489,37 @@ impl Notification {
 ///
 /// A success response is sent when a request has been processed successfully.
 /// It contains the result of the method call and the identifier from the request.
+///
+/// The result defaults to a [`Value`], but any serde type can be used.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Success {
+pub struct Success<R = Value> {
     /// JSON-RPC protocol version (always "2.0")
-    jsonrpc: String,
+    pub(crate) jsonrpc: String,
     /// The result of the method call
-    pub result: Value,
+    pub result: R,
     /// Client-established identifier matching the request
     pub id: Id,
 }
 
-impl Success {
+impl<R> Success<R> {
+    /// Creates a JSON-RPC 2.0 success response object
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier matching the request
+    /// * `result` - The result of the method call
+    ///
+    /// # Returns
+    ///
+    /// A new Success
+    pub fn new<I: Into<Id>>(id: I, result: R) -> Self {
+        Success {
+            jsonrpc: String::from("2.0"),
+            result,
+            id: id.into(),
+        }
+    }
+
     /// Gets the JSON-RPC protocol version
     ///
     /// # Returns
@@ -248,7 +537,7 @@ impl Success {
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Error {
     /// JSON-RPC protocol version (always "2.0")
-    jsonrpc: String,
+    pub(crate) jsonrpc: String,
     /// The error that occurred
     pub error: RpcError,
     /// Client-established identifier matching the request
@@ -274,7 +563,11 @@ impl Error {
 /// - Notification: A method call without an identifier (no response expected)
 /// - Success: A successful response to a request
 /// - Error: An error response to a request
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+///
+/// Deserialization reads the object members once and picks the variant from
+/// the members present: `method` with `id` is a Request, `method` alone is a
+/// Notification, otherwise `result` or `error` makes a response.
+#[derive(Clone, PartialEq, Debug, Serialize)]
 #[serde(untagged)]
 pub enum JsonRpc {
     /// Request object
@@ -579,61 +872,1287 @@ impl JsonRpc {
         use serde_json::from_str;
         from_str(input)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::{json, to_value};
+    /// Parses a JSON string into a JSON-RPC message, applying the given conformance checks
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    /// * `options` - The conformance checks to apply
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc or a serde_json error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{JsonRpc, ParseOptions};
+    ///
+    /// let options = ParseOptions {
+    ///     require_version: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let input = r#"{"jsonrpc":"1.0","method":"subtract","params":[42,23],"id":1}"#;
+    /// assert!(JsonRpc::parse_with(input, &options).is_err());
+    /// ```
+    pub fn parse_with(input: &str, options: &ParseOptions) -> SerdeResult<Self> {
+        let mut deserializer = serde_json::Deserializer::from_str(input);
+        let message = options.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(message)
+    }
 
-    #[test]
-    fn request() {
-        let jsonrpc = to_value(JsonRpc::request((), "test"))
-            .expect("Unable to turn request into a Json Value");
-        assert_eq!(
-            jsonrpc,
-            json!({
-                "id": null,
-                "jsonrpc": "2.0",
-                "method": "test"
-            })
-        );
+    /// Parses a JSON string into a JSON-RPC message, enforcing the specification strictly
+    ///
+    /// This is the same as calling [`JsonRpc::parse_with`] with [`ParseOptions::strict`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc or a serde_json error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::JsonRpc;
+    ///
+    /// let input = r#"{"jsonrpc":"2.0","result":19,"error":{"code":-32603,"message":"Internal error"},"id":1}"#;
+    /// assert!(JsonRpc::parse(input).is_ok());
+    /// assert!(JsonRpc::parse_strict(input).is_err());
+    /// ```
+    pub fn parse_strict(input: &str) -> SerdeResult<Self> {
+        Self::parse_with(input, &ParseOptions::strict())
     }
 
-    #[test]
-    fn request_with_params_vec() {
-        let jsonrpc = to_value(JsonRpc::request_with_params(
-            46714,
-            "test",
-            json!([true, false, false, true]),
-        ))
-        .expect("Unable to turn request_with_params_vec into a Json Value");
-        assert_eq!(
-            jsonrpc,
-            json!({
-                "id": 46714,
-                "jsonrpc": "2.0",
-                "method": "test",
-                "params": [true, false, false, true]
+    /// Parses a JSON string into a JSON-RPC message and collects its unknown members
+    ///
+    /// Members not defined by the specification are returned alongside the message,
+    /// unless `options.deny_unknown_members` is set, in which case they are rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    /// * `options` - The conformance checks to apply
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc and its unknown members, or a serde_json error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{JsonRpc, ParseOptions};
+    ///
+    /// let input = r#"{"jsonrpc":"2.0","method":"ping","trace":"abc"}"#;
+    /// let (_, unknown) = JsonRpc::parse_with_unknown(input, &ParseOptions::default()).unwrap();
+    /// assert_eq!(unknown["trace"], "abc");
+    /// ```
+    pub fn parse_with_unknown(
+        input: &str,
+        options: &ParseOptions,
+    ) -> SerdeResult<(Self, Map<String, Value>)> {
+        let mut unknown = Map::new();
+        let mut deserializer = serde_json::Deserializer::from_str(input);
+        let message = JsonRpcVisitor {
+            options,
+            unknown: Some(&mut unknown),
+        }
+        .deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok((message, unknown))
+    }
+
+    /// Parses a JSON string into a JSON-RPC message, mapping failures to spec errors
+    ///
+    /// Malformed JSON yields a parse error (-32700) and valid JSON that is not a
+    /// valid message yields an invalid request error (-32600). In the latter case
+    /// the `id` of the message is recovered when it can be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc or a ParseError
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{Error, JsonRpc};
+    ///
+    /// let err = JsonRpc::try_parse(r#"{"jsonrpc":"2.0","method":1,"id":3}"#).unwrap_err();
+    /// assert_eq!(err.code(), Error::invalid_request().code);
+    ///
+    /// let err = JsonRpc::try_parse(r#"{"jsonrpc":"2.0","method""#).unwrap_err();
+    /// assert_eq!(err.code(), Error::parse_error().code);
+    /// ```
+    pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+        Self::try_parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses a JSON string into a JSON-RPC message with the given conformance checks,
+    /// mapping failures to spec errors
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    /// * `options` - The conformance checks to apply
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc or a ParseError
+    pub fn try_parse_with(input: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        Self::parse_with(input, options).map_err(|err| {
+            parse_failure(err, || {
+                serde_json::from_str::<Value>(input).map(|value| recover_id(&value))
             })
-        );
+        })
     }
 
-    #[test]
-    fn request_with_params_map() {
-        let jsonrpc = to_value(JsonRpc::request_with_params(
-            String::from("alpha-gamma-06714"),
-            "test",
-            json!({
-                "key": "94151351-5651651658-56151351351",
-                "n": 5158,
-                "mean": 454.54
-            }),
-        ))
-        .expect("Unable to turn request_with_params_map into a Json Value");
-        assert_eq!(
-            jsonrpc,
-            json!({
+    /// Parses a JSON byte slice into a JSON-RPC message, mapping failures to spec errors
+    ///
+    /// The input does not need to be validated as UTF-8 beforehand: invalid
+    /// UTF-8 yields a parse error (-32700), like any other malformed JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON bytes to parse
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc or a ParseError
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::JsonRpc;
+    ///
+    /// let request = JsonRpc::parse_slice(br#"{"jsonrpc":"2.0","method":"ping","id":1}"#).unwrap();
+    /// assert_eq!(request, JsonRpc::request(1, "ping"));
+    ///
+    /// let err = JsonRpc::parse_slice(b"{\"jsonrpc\":\"2.0\",\"method\":\"\xff\"}").unwrap_err();
+    /// assert_eq!(err.code(), -32700);
+    /// ```
+    pub fn parse_slice(input: &[u8]) -> Result<Self, ParseError> {
+        let mut deserializer = serde_json::Deserializer::from_slice(input);
+        ParseOptions::default()
+            .deserialize(&mut deserializer)
+            .and_then(|message| deserializer.end().map(|()| message))
+            .map_err(|err| {
+                parse_failure(err, || {
+                    serde_json::from_slice::<Value>(input).map(|value| recover_id(&value))
+                })
+            })
+    }
+
+    /// Parses a JSON-RPC message from a stream of JSON, mapping failures to spec errors
+    ///
+    /// The whole stream is read; anything but whitespace after the message
+    /// yields a parse error (-32700), as does an I/O error.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read the JSON from, e.g. a file or a socket
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed JsonRpc or a ParseError
+    pub fn parse_reader<R: Read>(reader: R) -> Result<Self, ParseError> {
+        Self::from_value(read_value(reader)?)
+    }
+
+    /// Converts a JSON value into a JSON-RPC message, mapping failures to spec errors
+    ///
+    /// A value that is not a valid message yields an invalid request error
+    /// (-32600), carrying the `id` of the message when it can be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The JSON value to convert
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the JsonRpc or a ParseError
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::JsonRpc;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({"jsonrpc": "2.0", "method": "ping"});
+    /// assert_eq!(JsonRpc::from_value(value).unwrap(), JsonRpc::notification("ping"));
+    /// ```
+    pub fn from_value(value: Value) -> Result<Self, ParseError> {
+        Self::from_json(&value, &ParseOptions::default())
+    }
+
+    /// Builds a message from a parsed JSON value, recovering its `id` on failure
+    fn from_json(value: &Value, options: &ParseOptions) -> Result<Self, ParseError> {
+        options
+            .deserialize(value)
+            .map_err(|err| parse_failure(err, || Ok(recover_id(value))))
+    }
+
+    /// Parses a JSON string holding either a single message or a batch
+    ///
+    /// The shape is decided from the first non-whitespace character, then the
+    /// payload is parsed with [`JsonRpc::try_parse`] or [`Batch::parse`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed Message or a ParseError
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{JsonRpc, Message};
+    ///
+    /// let single = JsonRpc::parse_message(r#"{"jsonrpc":"2.0","method":"ping"}"#).unwrap();
+    /// assert!(matches!(single, Message::Single(_)));
+    ///
+    /// let batch = JsonRpc::parse_message(r#" [{"jsonrpc":"2.0","method":"ping"}]"#).unwrap();
+    /// assert!(matches!(batch, Message::Batch(_)));
+    /// ```
+    pub fn parse_message(input: &str) -> Result<Message, ParseError> {
+        Self::parse_message_with(input, &ParseOptions::default())
+    }
+
+    /// Parses a JSON string holding either a single message or a batch, applying
+    /// the given conformance checks
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    /// * `options` - The conformance checks to apply
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed Message or a ParseError
+    pub fn parse_message_with(input: &str, options: &ParseOptions) -> Result<Message, ParseError> {
+        if input
+            .trim_start_matches([' ', '\t', '\n', '\r'])
+            .starts_with('[')
+        {
+            Batch::parse_with(input, options).map(Message::Batch)
+        } else {
+            Self::try_parse_with(input, options).map(Message::Single)
+        }
+    }
+}
+
+/// A batch of JSON-RPC messages
+///
+/// Each entry of an incoming batch is parsed independently: a malformed entry
+/// is kept as a [`ParseError`] carrying its recovered `id`, and does not fail
+/// the other entries. Serializing a batch writes its valid entries only.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Batch {
+    entries: Vec<Result<JsonRpc, ParseError>>,
+}
+
+impl Batch {
+    /// Parses a JSON array into a batch of JSON-RPC messages
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed Batch, or a ParseError when the input
+    /// is not valid JSON, not an array, or an empty array
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::Batch;
+    ///
+    /// let input = r#"[{"jsonrpc":"2.0","method":"sum","params":[1,2,4],"id":"1"},{"foo":"boo"}]"#;
+    /// let batch = Batch::parse(input).unwrap();
+    /// assert_eq!(batch.len(), 2);
+    /// assert!(batch.iter().nth(1).unwrap().is_err());
+    ///
+    /// assert!(Batch::parse("[]").is_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses a JSON array into a batch of JSON-RPC messages, applying the given
+    /// conformance checks to each entry
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string to parse
+    /// * `options` - The conformance checks to apply
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed Batch or a ParseError
+    pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let value = serde_json::from_str(input).map_err(|err| parse_failure(err, || Ok(None)))?;
+        Self::from_json(value, options)
+    }
+
+    /// Parses a JSON byte slice into a batch of JSON-RPC messages
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON bytes to parse
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed Batch, or a ParseError when the input
+    /// is not valid JSON, not an array, or an empty array
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::Batch;
+    ///
+    /// let batch = Batch::parse_slice(br#"[{"jsonrpc":"2.0","method":"ping"},1]"#).unwrap();
+    /// assert_eq!(batch.messages().count(), 1);
+    /// ```
+    pub fn parse_slice(input: &[u8]) -> Result<Self, ParseError> {
+        let value = serde_json::from_slice(input).map_err(|err| parse_failure(err, || Ok(None)))?;
+        Self::from_json(value, &ParseOptions::default())
+    }
+
+    /// Parses a batch of JSON-RPC messages from a stream of JSON
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read the JSON from, e.g. a file or a socket
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the parsed Batch, or a ParseError when the stream
+    /// fails or holds invalid JSON, a non-array, or an empty array
+    pub fn parse_reader<R: Read>(reader: R) -> Result<Self, ParseError> {
+        Self::from_value(read_value(reader)?)
+    }
+
+    /// Converts a JSON array into a batch of JSON-RPC messages
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The JSON value to convert
+    ///
+    /// # Returns
+    ///
+    /// A Result containing either the Batch, or a ParseError when the value is
+    /// not an array, or an empty array
+    pub fn from_value(value: Value) -> Result<Self, ParseError> {
+        Self::from_json(value, &ParseOptions::default())
+    }
+
+    /// Builds a batch from a parsed JSON value, parsing each entry independently
+    fn from_json(value: Value, options: &ParseOptions) -> Result<Self, ParseError> {
+        let values = match value {
+            Value::Array(values) => values,
+            value => {
+                return Err(ParseError::new(
+                    RpcError::invalid_request().with_data("expected a batch array"),
+                    recover_id(&value),
+                ))
+            }
+        };
+        if values.is_empty() {
+            return Err(ParseError::new(
+                RpcError::invalid_request().with_data("empty batch"),
+                None,
+            ));
+        }
+
+        let entries = values
+            .iter()
+            .map(|value| JsonRpc::from_json(value, options))
+            .collect();
+        Ok(Batch { entries })
+    }
+
+    /// Builds a batch from already parsed entries
+    #[cfg(feature = "websocket")]
+    pub(crate) fn from_entries(entries: Vec<Result<JsonRpc, ParseError>>) -> Self {
+        Batch { entries }
+    }
+
+    /// Gets the number of entries in the batch, including malformed ones
+    ///
+    /// # Returns
+    ///
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the batch has no entries
+    ///
+    /// # Returns
+    ///
+    /// `true` if the batch is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the entries of the batch, in order
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the parsed messages and the failures of malformed entries
+    pub fn iter(&self) -> std::slice::Iter<'_, Result<JsonRpc, ParseError>> {
+        self.entries.iter()
+    }
+
+    /// Iterates over the valid messages of the batch, in order
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the parsed messages, skipping malformed entries
+    pub fn messages(&self) -> impl Iterator<Item = &JsonRpc> {
+        self.entries.iter().filter_map(|entry| entry.as_ref().ok())
+    }
+
+    /// Builds the batch response by handling each entry in order
+    ///
+    /// Malformed entries are answered with their error response. The handler is
+    /// called for every valid message, but its response is omitted for
+    /// notifications, as the specification requires.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - Produces the response to a message, if any
+    ///
+    /// # Returns
+    ///
+    /// The responses to send back, or None when there is nothing to send,
+    /// e.g. because all entries were notifications
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{Batch, JsonRpc};
+    /// use serde_json::json;
+    ///
+    /// let input = r#"[{"jsonrpc":"2.0","method":"notify_sum","params":[1,2,4]},{"jsonrpc":"2.0","method":"notify_hello","params":[7]}]"#;
+    /// let batch = Batch::parse(input).unwrap();
+    /// let response = batch.respond(|message| {
+    ///     message.get_id().map(|id| JsonRpc::success(id, &json!(null)))
+    /// });
+    /// assert_eq!(response, None);
+    /// ```
+    pub fn respond<F>(self, mut handler: F) -> Option<Vec<JsonRpc>>
+    where
+        F: FnMut(JsonRpc) -> Option<JsonRpc>,
+    {
+        let responses: Vec<JsonRpc> = self
+            .entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Ok(message) => {
+                    let notification = matches!(message, JsonRpc::Notification(_));
+                    handler(message).filter(|_| !notification)
+                }
+                Err(err) => Some(err.into_response()),
+            })
+            .collect();
+
+        if responses.is_empty() {
+            None
+        } else {
+            Some(responses)
+        }
+    }
+}
+
+impl From<Vec<JsonRpc>> for Batch {
+    /// Converts a vector of messages to a Batch
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages of the batch
+    ///
+    /// # Returns
+    ///
+    /// A new Batch containing the provided messages
+    fn from(messages: Vec<JsonRpc>) -> Self {
+        Batch {
+            entries: messages.into_iter().map(Ok).collect(),
+        }
+    }
+}
+
+impl IntoIterator for Batch {
+    type Item = Result<JsonRpc, ParseError>;
+    type IntoIter = std::vec::IntoIter<Result<JsonRpc, ParseError>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Batch {
+    type Item = &'a Result<JsonRpc, ParseError>;
+    type IntoIter = std::slice::Iter<'a, Result<JsonRpc, ParseError>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl Serialize for Batch {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.messages())
+    }
+}
+
+/// A JSON-RPC payload: either a single message or a batch
+///
+/// Serializes to a single object or an array of objects respectively.
+#[derive(Clone, PartialEq, Debug, Serialize)]
+#[serde(untagged)]
+pub enum Message {
+    /// A single message object
+    Single(JsonRpc),
+    /// An array of message objects
+    Batch(Batch),
+}
+
+impl Message {
+    /// Builds a single message or a batch from a decoded JSON value, deciding
+    /// the shape from the value
+    #[cfg(any(feature = "cbor", feature = "msgpack", feature = "simd"))]
+    pub(crate) fn from_json(value: Value, options: &ParseOptions) -> Result<Self, ParseError> {
+        if value.is_array() {
+            Batch::from_json(value, options).map(Message::Batch)
+        } else {
+            JsonRpc::from_json(&value, options).map(Message::Single)
+        }
+    }
+
+    /// Builds the response to the payload by handling each message
+    ///
+    /// Responses to notifications are omitted, and malformed batch entries are
+    /// answered with their error response, see [`Batch::respond`].
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - Produces the response to a message, if any
+    ///
+    /// # Returns
+    ///
+    /// The response payload of the same shape, or None when there is nothing to send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::{JsonRpc, Message};
+    /// use serde_json::json;
+    ///
+    /// let message = JsonRpc::parse_message(r#"{"jsonrpc":"2.0","method":"ping","id":1}"#).unwrap();
+    /// let response = message.respond(|request| {
+    ///     request.get_id().map(|id| JsonRpc::success(id, &json!("pong")))
+    /// });
+    /// assert_eq!(response, Some(Message::Single(JsonRpc::success(1, &json!("pong")))));
+    /// ```
+    pub fn respond<F>(self, mut handler: F) -> Option<Message>
+    where
+        F: FnMut(JsonRpc) -> Option<JsonRpc>,
+    {
+        match self {
+            Message::Single(JsonRpc::Notification(notification)) => {
+                handler(JsonRpc::Notification(notification));
+                None
+            }
+            Message::Single(message) => handler(message).map(Message::Single),
+            Message::Batch(batch) => batch
+                .respond(handler)
+                .map(|responses| Message::Batch(responses.into())),
+        }
+    }
+}
+
+impl From<JsonRpc> for Message {
+    /// Converts a JsonRpc to Message::Single
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to convert
+    ///
+    /// # Returns
+    ///
+    /// A new Message::Single variant containing the provided message
+    fn from(message: JsonRpc) -> Self {
+        Message::Single(message)
+    }
+}
+
+impl From<Batch> for Message {
+    /// Converts a Batch to Message::Batch
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The batch to convert
+    ///
+    /// # Returns
+    ///
+    /// A new Message::Batch variant containing the provided batch
+    fn from(batch: Batch) -> Self {
+        Message::Batch(batch)
+    }
+}
+
+impl From<Vec<JsonRpc>> for Message {
+    /// Converts a vector of messages to Message::Batch
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages of the batch
+    ///
+    /// # Returns
+    ///
+    /// A new Message::Batch variant containing the provided messages
+    fn from(messages: Vec<JsonRpc>) -> Self {
+        Message::Batch(messages.into())
+    }
+}
+
+/// Conformance checks applied while parsing a JSON-RPC message
+///
+/// The default options are lenient and match [`JsonRpc::parse`]: any `jsonrpc`
+/// string is accepted, a response carrying both `result` and `error` is read
+/// as a Success, `"params": null` is read as no params and unknown members are
+/// ignored. [`ParseOptions::strict`] enables every check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Require the `jsonrpc` member to be exactly "2.0"
+    pub require_version: bool,
+    /// Require a response to carry exactly one of `result` and `error`
+    pub exclusive_response: bool,
+    /// Require `params`, when present, to be an array or an object
+    pub structured_params: bool,
+    /// Reject members that are not defined by the specification, or that
+    /// do not belong to the kind of message being parsed
+    pub deny_unknown_members: bool,
+}
+
+impl ParseOptions {
+    /// Creates options enforcing the JSON-RPC 2.0 specification strictly
+    ///
+    /// # Returns
+    ///
+    /// A new `ParseOptions` with every conformance check enabled
+    pub fn strict() -> Self {
+        ParseOptions {
+            require_version: true,
+            exclusive_response: true,
+            structured_params: true,
+            deny_unknown_members: true,
+        }
+    }
+}
+
+/// Maps a serde_json failure to the spec error a server must answer with
+///
+/// serde_json stops at the first error, so a data error may hide a syntax
+/// error further on, e.g. a wrongly typed `method` before trailing garbage.
+/// For data errors `reparse` reads the input again as a plain JSON value: if
+/// that fails too the input is malformed JSON, otherwise it yields the `id`
+/// of the offending message.
+fn parse_failure<F>(err: serde_json::Error, reparse: F) -> ParseError
+where
+    F: FnOnce() -> SerdeResult<Option<Id>>,
+{
+    use serde_json::error::Category;
+
+    match err.classify() {
+        Category::Data => match reparse() {
+            Ok(id) => ParseError::new(RpcError::invalid_request().with_data(err.to_string()), id),
+            Err(err) => ParseError::new(RpcError::parse_error().with_data(err.to_string()), None),
+        },
+        Category::Io | Category::Syntax | Category::Eof => {
+            ParseError::new(RpcError::parse_error().with_data(err.to_string()), None)
+        }
+    }
+}
+
+/// Serializes a payload to compact JSON for the transports
+///
+/// A payload only holds JSON values, string keys and ids that serde_json
+/// reads back, so serializing it cannot fail.
+pub(crate) fn encode_message(message: &Message) -> String {
+    serde_json::to_string(message).expect("JSON-RPC payloads always serialize to JSON")
+}
+
+/// Reads the `id` member of a message that failed to parse, if it is a valid id
+fn recover_id(value: &Value) -> Option<Id> {
+    value.get("id").and_then(|id| Id::deserialize(id).ok())
+}
+
+/// Reads a complete JSON value from a stream, mapping failures to parse errors
+fn read_value<R: Read>(reader: R) -> Result<Value, ParseError> {
+    serde_json::from_reader(reader).map_err(|err| parse_failure(err, || Ok(None)))
+}
+
+/// Members of a JSON-RPC 2.0 message object
+///
+/// The name of an unknown member is borrowed from the input when possible,
+/// so skipping it does not allocate.
+enum Member<'de> {
+    Jsonrpc,
+    Method,
+    Params,
+    Id,
+    Result,
+    Error,
+    Unknown(Cow<'de, str>),
+}
+
+/// Member names defined by the JSON-RPC 2.0 specification
+const MEMBERS: &[&str] = &["jsonrpc", "method", "params", "id", "result", "error"];
+
+impl Member<'_> {
+    /// Recognizes a member name defined by the specification
+    fn known(name: &str) -> Option<Member<'static>> {
+        Some(match name {
+            "jsonrpc" => Member::Jsonrpc,
+            "method" => Member::Method,
+            "params" => Member::Params,
+            "id" => Member::Id,
+            "result" => Member::Result,
+            "error" => Member::Error,
+            _ => return None,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Member<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MemberVisitor;
+
+        impl<'de> Visitor<'de> for MemberVisitor {
+            type Value = Member<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a member name")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Member<'de>, E> {
+                Ok(Member::known(v).unwrap_or(Member::Unknown(Cow::Borrowed(v))))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Member<'de>, E> {
+                Ok(Member::known(v).unwrap_or_else(|| Member::Unknown(Cow::Owned(v.to_owned()))))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Member<'de>, E> {
+                Ok(match Member::known(&v) {
+                    Some(member) => member,
+                    None => Member::Unknown(Cow::Owned(v)),
+                })
+            }
+        }
+
+        deserializer.deserialize_identifier(MemberVisitor)
+    }
+}
+
+/// Deserializes a string member, borrowing it from the input when possible and
+/// naming the member when the value is not a string
+struct StringMember(&'static str);
+
+impl<'de> DeserializeSeed<'de> for StringMember {
+    type Value = Cow<'de, str>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for StringMember {
+    type Value = Cow<'de, str>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a string `{}` member", self.0)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(Cow::Borrowed(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdVisitor;
+
+        impl<'de> Visitor<'de> for IdVisitor {
+            type Value = Id;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string, number or null `id` member")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Id, E> {
+                Ok(Id::Num(v.into()))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Id, E> {
+                Ok(Id::Num(v.into()))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Id, E> {
+                Number::from_f64(v)
+                    .map(Id::Num)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Float(v), &self))
+            }
+
+            /// Numbers come as a single-entry map when `arbitrary_precision` is enabled
+            #[cfg(feature = "arbitrary_precision")]
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Id, A::Error> {
+                Number::deserialize(de::value::MapAccessDeserializer::new(map)).map(Id::Num)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Id, E> {
+                Ok(Id::Str(v.to_owned()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Id, E> {
+                Ok(Id::Str(v))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Id, E> {
+                Ok(Id::None(()))
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Id, E> {
+                Ok(Id::None(()))
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Id, D::Error> {
+                Id::deserialize(deserializer)
+            }
+        }
+
+        deserializer.deserialize_any(IdVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Params {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ParamsVisitor;
+
+        impl<'de> Visitor<'de> for ParamsVisitor {
+            type Value = Params;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array or object `params` member")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Params, A::Error> {
+                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(Params::Array(values))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Params, A::Error> {
+                let mut values = Map::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    // Numbers come as a single-entry map when `arbitrary_precision` is enabled
+                    #[cfg(feature = "arbitrary_precision")]
+                    if values.is_empty() && key == "$serde_json::private::Number" {
+                        return Err(de::Error::invalid_type(
+                            de::Unexpected::Other("number"),
+                            &self,
+                        ));
+                    }
+                    values.insert(key, map.next_value()?);
+                }
+                Ok(Params::Map(values))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Params, E> {
+                Ok(Params::None(()))
+            }
+
+            /// Some formats, such as CBOR, hand a null over as an absent option
+            fn visit_none<E: de::Error>(self) -> Result<Params, E> {
+                Ok(Params::None(()))
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Params, D::Error> {
+                deserializer.deserialize_any(self)
+            }
+        }
+
+        deserializer.deserialize_any(ParamsVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonRpc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ParseOptions::default().deserialize(deserializer)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &ParseOptions {
+    type Value = JsonRpc;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonRpc, D::Error> {
+        deserializer.deserialize_map(JsonRpcVisitor {
+            options: self,
+            unknown: None,
+        })
+    }
+}
+
+/// Reads the members of a message object once and decides the variant from them
+struct JsonRpcVisitor<'a> {
+    options: &'a ParseOptions,
+    /// Receives the members not defined by the specification, if collecting
+    unknown: Option<&'a mut Map<String, Value>>,
+}
+
+impl<'de> DeserializeSeed<'de> for JsonRpcVisitor<'_> {
+    type Value = JsonRpc;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonRpc, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for JsonRpcVisitor<'_> {
+    type Value = JsonRpc;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON-RPC 2.0 message object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<JsonRpc, A::Error> {
+        Ok(match read_message(map, self.options, self.unknown)? {
+            Parts::Request {
+                jsonrpc,
+                method,
+                params,
+                id,
+            } => JsonRpc::Request(Request {
+                jsonrpc: jsonrpc.into_owned(),
+                method: method.into_owned(),
+                params,
+                id,
+            }),
+            Parts::Notification {
+                jsonrpc,
+                method,
+                params,
+            } => JsonRpc::Notification(Notification {
+                jsonrpc: jsonrpc.into_owned(),
+                method: method.into_owned(),
+                params,
+            }),
+            Parts::Success {
+                jsonrpc,
+                result,
+                id,
+            } => JsonRpc::Success(Success {
+                jsonrpc: jsonrpc.into_owned(),
+                result,
+                id,
+            }),
+            Parts::Error { jsonrpc, error, id } => JsonRpc::Error(Error {
+                jsonrpc: jsonrpc.into_owned(),
+                error,
+                id,
+            }),
+        })
+    }
+}
+
+/// The members of a message object, sorted into the kind of message they make
+///
+/// Generic over the params, result and error payloads, so that the owned and
+/// the borrowed messages share the same member handling and checks.
+pub(crate) enum Parts<'de, P, R, E> {
+    Request {
+        jsonrpc: Cow<'de, str>,
+        method: Cow<'de, str>,
+        params: Option<P>,
+        id: Id,
+    },
+    Notification {
+        jsonrpc: Cow<'de, str>,
+        method: Cow<'de, str>,
+        params: Option<P>,
+    },
+    Success {
+        jsonrpc: Cow<'de, str>,
+        result: R,
+        id: Id,
+    },
+    Error {
+        jsonrpc: Cow<'de, str>,
+        error: E,
+        id: Id,
+    },
+}
+
+/// A `params` or `error` payload read by [`read_message`]
+///
+/// Owned payloads check their shape while deserializing, while raw payloads
+/// accept any JSON value. Raw payloads are checked afterwards against the
+/// owned types, so that both parsers reject the same messages with the same
+/// error.
+pub(crate) trait Payload {
+    /// Checks the payload of a `params` member
+    ///
+    /// Returns the message of the error the owned `Params` would report.
+    fn check_params(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Checks the payload of an `error` member
+    ///
+    /// Returns the message of the error the owned `Error` would report.
+    fn check_error(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl Payload for Params {}
+
+impl Payload for RpcError {}
+
+/// Gets the message of a serde_json error, without the position it appends
+pub(crate) fn error_message(err: serde_json::Error) -> String {
+    let message = err.to_string();
+    let position = format!(" at line {} column {}", err.line(), err.column());
+    match message.strip_suffix(&position) {
+        Some(stripped) => stripped.to_owned(),
+        None => message,
+    }
+}
+
+/// Reads the members of a message object once, applies the conformance checks
+/// and decides the kind of message from the members present
+///
+/// Unknown members are collected into `unknown` when given, and skipped otherwise.
+pub(crate) fn read_message<'de, A, P, R, E>(
+    mut map: A,
+    options: &ParseOptions,
+    mut unknown: Option<&mut Map<String, Value>>,
+) -> Result<Parts<'de, P, R, E>, A::Error>
+where
+    A: MapAccess<'de>,
+    P: Deserialize<'de> + Payload,
+    R: Deserialize<'de>,
+    E: Deserialize<'de> + Payload,
+{
+    let mut jsonrpc: Option<Cow<'de, str>> = None;
+    let mut method: Option<Cow<'de, str>> = None;
+    let mut params: Option<Option<P>> = None;
+    let mut id: Option<Id> = None;
+    let mut result: Option<R> = None;
+    let mut error: Option<E> = None;
+
+    while let Some(member) = map.next_key::<Member>()? {
+        match member {
+            Member::Jsonrpc => {
+                if jsonrpc.is_some() {
+                    return Err(de::Error::duplicate_field("jsonrpc"));
+                }
+                jsonrpc = Some(map.next_value_seed(StringMember("jsonrpc"))?);
+            }
+            Member::Method => {
+                if method.is_some() {
+                    return Err(de::Error::duplicate_field("method"));
+                }
+                method = Some(map.next_value_seed(StringMember("method"))?);
+            }
+            Member::Params => {
+                if params.is_some() {
+                    return Err(de::Error::duplicate_field("params"));
+                }
+                let value: Option<P> = map.next_value()?;
+                if let Some(ref value) = value {
+                    value.check_params().map_err(de::Error::custom)?;
+                }
+                params = Some(value);
+            }
+            Member::Id => {
+                if id.is_some() {
+                    return Err(de::Error::duplicate_field("id"));
+                }
+                id = Some(map.next_value()?);
+            }
+            Member::Result => {
+                if result.is_some() {
+                    return Err(de::Error::duplicate_field("result"));
+                }
+                result = Some(map.next_value()?);
+            }
+            Member::Error => {
+                if error.is_some() {
+                    return Err(de::Error::duplicate_field("error"));
+                }
+                let value: E = map.next_value()?;
+                value.check_error().map_err(de::Error::custom)?;
+                error = Some(value);
+            }
+            Member::Unknown(name) => {
+                if options.deny_unknown_members {
+                    return Err(de::Error::unknown_field(&name, MEMBERS));
+                }
+                match unknown {
+                    Some(ref mut unknown) => {
+                        unknown.insert(name.into_owned(), map.next_value()?);
+                    }
+                    None => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+            }
+        }
+    }
+
+    let jsonrpc = jsonrpc.ok_or_else(|| de::Error::missing_field("jsonrpc"))?;
+    if options.require_version && jsonrpc != "2.0" {
+        return Err(de::Error::invalid_value(
+            de::Unexpected::Str(&jsonrpc),
+            &"`jsonrpc` to be exactly \"2.0\"",
+        ));
+    }
+
+    if let Some(method) = method {
+        if options.deny_unknown_members {
+            if result.is_some() {
+                return Err(de::Error::custom("unexpected member `result` in a request"));
+            }
+            if error.is_some() {
+                return Err(de::Error::custom("unexpected member `error` in a request"));
+            }
+        }
+        if options.structured_params && matches!(params, Some(None)) {
+            return Err(de::Error::invalid_type(
+                de::Unexpected::Unit,
+                &"an array or object `params` member",
+            ));
+        }
+        let params = params.flatten();
+        return Ok(match id {
+            Some(id) => Parts::Request {
+                jsonrpc,
+                method,
+                params,
+                id,
+            },
+            None => Parts::Notification {
+                jsonrpc,
+                method,
+                params,
+            },
+        });
+    }
+
+    if options.deny_unknown_members && params.is_some() {
+        return Err(de::Error::custom(
+            "unexpected member `params` in a response",
+        ));
+    }
+    if options.exclusive_response && result.is_some() && error.is_some() {
+        return Err(de::Error::custom(
+            "a response must not contain both `result` and `error`",
+        ));
+    }
+    let missing_id = || de::Error::missing_field("id");
+    match (result, error) {
+        (Some(result), _) => Ok(Parts::Success {
+            jsonrpc,
+            result,
+            id: id.ok_or_else(missing_id)?,
+        }),
+        (None, Some(error)) => Ok(Parts::Error {
+            jsonrpc,
+            error,
+            id: id.ok_or_else(missing_id)?,
+        }),
+        (None, None) => Err(de::Error::custom(
+            "missing field `method`, `result` or `error`",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_value};
+
+    #[test]
+    fn request() {
+        let jsonrpc = to_value(JsonRpc::request((), "test"))
+            .expect("Unable to turn request into a Json Value");
+        assert_eq!(
+            jsonrpc,
+            json!({
+                "id": null,
+                "jsonrpc": "2.0",
+                "method": "test"
+            })
+        );
+    }
+
+    #[test]
+    fn request_with_params_vec() {
+        let jsonrpc = to_value(JsonRpc::request_with_params(
+            46714,
+            "test",
+            json!([true, false, false, true]),
+        ))
+        .expect("Unable to turn request_with_params_vec into a Json Value");
+        assert_eq!(
+            jsonrpc,
+            json!({
+                "id": 46714,
+                "jsonrpc": "2.0",
+                "method": "test",
+                "params": [true, false, false, true]
+            })
+        );
+    }
+
+    #[test]
+    fn request_with_params_map() {
+        let jsonrpc = to_value(JsonRpc::request_with_params(
+            String::from("alpha-gamma-06714"),
+            "test",
+            json!({
+                "key": "94151351-5651651658-56151351351",
+                "n": 5158,
+                "mean": 454.54
+            }),
+        ))
+        .expect("Unable to turn request_with_params_map into a Json Value");
+        assert_eq!(
+            jsonrpc,
+            json!({
                 "id": "alpha-gamma-06714",
                 "jsonrpc": "2.0",
                 "method": "test",
@@ -645,4 +2164,248 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn deserialize_variants() {
+        let request = JsonRpc::parse(r#"{"jsonrpc":"2.0","method":"sum","params":[1,2],"id":1}"#)
+            .expect("Unable to parse request");
+        assert_eq!(
+            request,
+            JsonRpc::request_with_params(1, "sum", json!([1, 2]))
+        );
+
+        let notification = JsonRpc::parse(r#"{"jsonrpc":"2.0","method":"ping","params":null}"#)
+            .expect("Unable to parse notification");
+        assert_eq!(notification, JsonRpc::notification("ping"));
+
+        let success = JsonRpc::parse(r#"{"jsonrpc":"2.0","result":null,"id":"a"}"#)
+            .expect("Unable to parse success");
+        assert_eq!(success, JsonRpc::success(String::from("a"), &Value::Null));
+
+        let error = JsonRpc::parse(
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":null}"#,
+        )
+        .expect("Unable to parse error");
+        assert_eq!(error, JsonRpc::error((), RpcError::method_not_found()));
+    }
+
+    #[test]
+    fn deserialize_errors_name_member() {
+        let message = |input: &str| JsonRpc::parse(input).unwrap_err().to_string();
+
+        assert!(message(r#"{"method":"ping"}"#).starts_with("missing field `jsonrpc`"));
+        assert!(message(r#"{"jsonrpc":"2.0","result":1}"#).starts_with("missing field `id`"));
+        assert!(message(r#"{"jsonrpc":"2.0","id":1}"#)
+            .starts_with("missing field `method`, `result` or `error`"));
+        assert!(message(r#"{"jsonrpc":"2.0","method":1}"#).contains("`method`"));
+        assert!(message(r#"{"jsonrpc":"2.0","method":"a","params":3}"#).contains("`params`"));
+        assert!(message(r#"{"jsonrpc":"2.0","method":"a","id":true}"#).contains("`id`"));
+        assert!(message(r#"{"jsonrpc":"2.0","method":"a","method":"b"}"#)
+            .starts_with("duplicate field `method`"));
+        assert!(message("[]").contains("a JSON-RPC 2.0 message object"));
+    }
+
+    #[test]
+    fn parse_strict() {
+        let strict = |input: &str| JsonRpc::parse_strict(input).map_err(|e| e.to_string());
+
+        assert!(strict(r#"{"jsonrpc":"2.0","method":"a","params":{},"id":1}"#).is_ok());
+        assert!(strict(r#"{"jsonrpc":"1.0","method":"a","id":1}"#)
+            .unwrap_err()
+            .contains("\"2.0\""));
+        assert!(strict(r#"{"jsonrpc":"2.0","method":"a","params":null}"#)
+            .unwrap_err()
+            .contains("`params`"));
+        assert!(
+            strict(r#"{"jsonrpc":"2.0","result":1,"error":{"code":1,"message":""},"id":1}"#)
+                .unwrap_err()
+                .contains("both `result` and `error`")
+        );
+        assert!(strict(r#"{"jsonrpc":"2.0","method":"a","extra":1}"#)
+            .unwrap_err()
+            .starts_with("unknown field `extra`"));
+        assert!(strict(r#"{"jsonrpc":"2.0","method":"a","result":1}"#)
+            .unwrap_err()
+            .contains("`result`"));
+    }
+
+    #[test]
+    fn parse_with_unknown() {
+        let input = r#"{"jsonrpc":"2.0","result":1,"id":1,"meta":{"trace":7}}"#;
+        let (message, unknown) = JsonRpc::parse_with_unknown(input, &ParseOptions::default())
+            .expect("Unable to parse message with unknown members");
+        assert_eq!(message, JsonRpc::success(1, &json!(1)));
+        assert_eq!(Value::Object(unknown), json!({"meta": {"trace": 7}}));
+    }
+
+    #[test]
+    fn try_parse_errors() {
+        let err = JsonRpc::try_parse(r#"{"jsonrpc":"2.0","method":"a","params":1,"id":"x"}"#)
+            .expect_err("Invalid params member was accepted");
+        assert_eq!(err.code(), -32600);
+        assert_eq!(err.id, Some(Id::Str(String::from("x"))));
+        assert_eq!(
+            to_value(err.into_response()).expect("Unable to turn response into a Json Value")["id"],
+            json!("x")
+        );
+
+        let err = JsonRpc::try_parse(r#"{"jsonrpc":"2.0","method":"a","id":[1]}"#)
+            .expect_err("Invalid id member was accepted");
+        assert_eq!(err.code(), -32600);
+        assert_eq!(err.id, None);
+
+        let err = JsonRpc::try_parse(r#"{"jsonrpc":"2.0","method":"a","id":1"#)
+            .expect_err("Truncated input was accepted");
+        assert_eq!(err.code(), -32700);
+        assert_eq!(err.id, None);
+        assert_eq!(err.into_response().get_id(), Some(Id::None(())));
+
+        for input in [
+            r#"{"jsonrpc":"2.0","method":1,"id":1,"#,
+            r#"{"jsonrpc":"2.0","method":1,"id":1} garbage"#,
+        ] {
+            let err = JsonRpc::try_parse(input).expect_err("Malformed input was accepted");
+            assert_eq!((err.code(), err.id), (-32700, None));
+        }
+    }
+
+    #[test]
+    fn batch_respond() {
+        let input = r#"[
+            {"jsonrpc":"2.0","method":"sum","params":[1,2,4],"id":"1"},
+            {"jsonrpc":"2.0","method":"notify_hello","params":[7]},
+            {"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":"2"},
+            {"foo":"boo"},
+            {"jsonrpc":"2.0","method":1,"params":{"name":"myself"},"id":"5"}
+        ]"#;
+        let batch = Batch::parse(input).expect("Unable to parse batch");
+        assert_eq!(batch.len(), 5);
+        assert_eq!(batch.messages().count(), 3);
+
+        let responses = batch
+            .respond(|message| message.get_id().map(|id| JsonRpc::success(id, &json!(7))))
+            .expect("Batch response is empty");
+        let ids: Vec<_> = responses.iter().map(|r| r.get_id()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                Some(Id::from(String::from("1"))),
+                Some(Id::from(String::from("2"))),
+                Some(Id::None(())),
+                Some(Id::from(String::from("5"))),
+            ]
+        );
+        assert_eq!(responses[2].get_error().map(|e| e.code), Some(-32600));
+    }
+
+    #[test]
+    fn batch_invalid() {
+        assert_eq!(Batch::parse("[]").unwrap_err().code(), -32600);
+        assert_eq!(
+            Batch::parse(r#"{"jsonrpc":"2.0"}"#).unwrap_err().code(),
+            -32600
+        );
+        assert_eq!(Batch::parse("[1,").unwrap_err().code(), -32700);
+
+        let batch = Batch::parse("[1,2]").expect("Unable to parse batch");
+        let responses = batch.respond(|_| None).expect("Batch response is empty");
+        assert_eq!(responses.len(), 2);
+    }
+
+    #[test]
+    fn parse_slices_readers_and_values() {
+        let input = r#"{"jsonrpc":"2.0","method":"a","params":[1],"id":"x"}"#;
+        let expected = JsonRpc::parse(input).expect("Unable to parse message");
+        assert_eq!(JsonRpc::parse_slice(input.as_bytes()), Ok(expected.clone()));
+        assert_eq!(
+            JsonRpc::parse_reader(input.as_bytes()),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            JsonRpc::from_value(serde_json::from_str(input).unwrap()),
+            Ok(expected.clone())
+        );
+
+        let invalid = r#"{"jsonrpc":"2.0","method":1,"id":"x"}"#;
+        for err in [
+            JsonRpc::parse_slice(invalid.as_bytes()).unwrap_err(),
+            JsonRpc::parse_reader(invalid.as_bytes()).unwrap_err(),
+            JsonRpc::from_value(serde_json::from_str(invalid).unwrap()).unwrap_err(),
+        ] {
+            assert_eq!(
+                (err.code(), err.id),
+                (-32600, Some(Id::from(String::from("x"))))
+            );
+        }
+        for input in [
+            &b"{\"jsonrpc\":\"2.0\",\"method\":\"\xff\"}"[..],
+            br#"{"jsonrpc":"2.0","method":"a"} 1"#,
+            br#"{"jsonrpc":"2.0","method":1,"id":1,"#,
+            br#"{"jsonrpc":"2.0","method":1,"id":1} garbage"#,
+            b"{",
+        ] {
+            assert_eq!(JsonRpc::parse_slice(input).unwrap_err().code(), -32700);
+            assert_eq!(JsonRpc::parse_reader(input).unwrap_err().code(), -32700);
+        }
+
+        let batch = format!("[{},{}]", input, invalid);
+        let expected = Batch::parse(&batch).expect("Unable to parse batch");
+        assert_eq!(Batch::parse_slice(batch.as_bytes()), Ok(expected.clone()));
+        assert_eq!(Batch::parse_reader(batch.as_bytes()), Ok(expected.clone()));
+        assert_eq!(
+            Batch::from_value(serde_json::from_str(&batch).unwrap()),
+            Ok(expected)
+        );
+        assert_eq!(Batch::parse_slice(b"[1,").unwrap_err().code(), -32700);
+        assert_eq!(
+            Batch::parse_slice(br#"[{"jsonrpc":"2.0","method":1,"id":1}] garbage"#)
+                .unwrap_err()
+                .code(),
+            -32700
+        );
+        assert_eq!(Batch::parse_reader(&b"[]"[..]).unwrap_err().code(), -32600);
+        assert_eq!(Batch::from_value(json!({})).unwrap_err().code(), -32600);
+    }
+
+    #[test]
+    fn numeric_ids_round_trip() {
+        for raw in ["18446744073709551615", "-9223372036854775808", "1.5", "7"] {
+            let input = format!(r#"{{"jsonrpc":"2.0","method":"a","id":{}}}"#, raw);
+            let request = JsonRpc::parse(&input).expect("Unable to parse numeric id");
+            let id = request.get_id().expect("Request has no id");
+            assert_eq!(id.is_fractional(), raw.contains('.'));
+
+            let response = serde_json::to_string(&JsonRpc::success(id, &Value::Null))
+                .expect("Unable to serialize response");
+            assert!(response.ends_with(&format!(r#""id":{}}}"#, raw)));
+        }
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn big_numeric_id_round_trip() {
+        let input = r#"{"jsonrpc":"2.0","result":null,"id":123456789012345678901234567890}"#;
+        let response = JsonRpc::parse(input).expect("Unable to parse big numeric id");
+        assert_eq!(serde_json::to_string(&response).unwrap(), input);
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn numeric_id_echoes_verbatim() {
+        // serde_json always spells out the sign of an exponent when it reads one
+        for (id, echoed) in [
+            ("1e2", "1e+2"),
+            ("1e-2", "1e-2"),
+            ("0.10", "0.10"),
+            ("-0", "-0"),
+        ] {
+            let input = format!(r#"{{"jsonrpc":"2.0","method":"a","id":{}}}"#, id);
+            let request = JsonRpc::parse(&input).expect("Unable to parse numeric id");
+            let response = JsonRpc::success(request.get_id().unwrap(), &Value::Null);
+            assert_eq!(
+                serde_json::to_string(&response).unwrap(),
+                format!(r#"{{"jsonrpc":"2.0","result":null,"id":{}}}"#, echoed)
+            );
+        }
+    }
 }
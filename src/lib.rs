@@ -9,8 +9,35 @@
 
 //! JSON-RPC 2.0 Specification serialization for Rust.
 
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+mod binary;
+pub mod borrowed;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "async")]
+pub mod client;
 pub mod error;
+pub mod framing;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod id;
 pub mod jsonrpc;
+#[cfg(feature = "async")]
+pub mod memory;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod router;
+pub mod server;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
-pub use error::{Error, ErrorCode, Result};
+pub use borrowed::JsonRpcRef;
+#[cfg(feature = "async")]
+pub use client::{Client, ClientError};
+pub use error::{Error, ErrorCode, ParseError, Result};
 pub use jsonrpc::*;
+#[cfg(feature = "async")]
+pub use router::AsyncRouter;
+pub use router::Router;
@@ -0,0 +1,285 @@
+//! In-memory JSON-RPC 2.0 transport
+//!
+//! This module connects two endpoints through in-process channels, so that
+//! client and server code can be tested without sockets. `pair` hands the
+//! messages over as they are, while `pair_encoded` serializes them to JSON
+//! text and parses them on receipt, exercising the real encoding.
+
+use std::error;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_util::{FutureExt, Stream, StreamExt};
+
+use crate::error::ParseError;
+use crate::jsonrpc::{encode_message, JsonRpc, Message};
+use crate::router::AsyncRouter;
+
+/// Creates two connected endpoints exchanging messages as they are
+///
+/// # Returns
+///
+/// Both endpoints; what one sends, the other receives
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::memory;
+/// use jsonrpc_lite::{JsonRpc, Message};
+///
+/// let (client, mut server) = memory::pair();
+/// client.send(JsonRpc::request(1, "ping")).unwrap();
+/// assert_eq!(server.try_recv(), Some(Ok(Message::Single(JsonRpc::request(1, "ping")))));
+/// ```
+pub fn pair() -> (Endpoint, Endpoint) {
+    pair_with(false)
+}
+
+/// Creates two connected endpoints exchanging messages as serialized JSON text
+///
+/// # Returns
+///
+/// Both endpoints; what one sends, the other receives
+pub fn pair_encoded() -> (Endpoint, Endpoint) {
+    pair_with(true)
+}
+
+fn pair_with(encoded: bool) -> (Endpoint, Endpoint) {
+    let (left_sender, right_receiver) = mpsc::unbounded();
+    let (right_sender, left_receiver) = mpsc::unbounded();
+    let left = Endpoint {
+        sender: left_sender,
+        receiver: left_receiver,
+        encoded,
+    };
+    let right = Endpoint {
+        sender: right_sender,
+        receiver: right_receiver,
+        encoded,
+    };
+    (left, right)
+}
+
+/// A payload in flight between two endpoints
+#[derive(Debug)]
+enum Frame {
+    Message(Message),
+    Text(String),
+}
+
+impl Frame {
+    fn into_message(self) -> Result<Message, ParseError> {
+        match self {
+            Frame::Message(message) => Ok(message),
+            Frame::Text(text) => JsonRpc::parse_message(&text),
+        }
+    }
+}
+
+/// One end of an in-memory transport
+///
+/// Also a stream of the payloads received, ending once the other endpoint is
+/// dropped and every payload it sent is received.
+#[derive(Debug)]
+pub struct Endpoint {
+    sender: mpsc::UnboundedSender<Frame>,
+    receiver: mpsc::UnboundedReceiver<Frame>,
+    encoded: bool,
+}
+
+impl Endpoint {
+    /// Sends a payload to the other endpoint
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The payload to send, e.g. a `JsonRpc` or a `Vec<JsonRpc>`
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once queued, or `Disconnected` if the other endpoint is dropped
+    pub fn send<M: Into<Message>>(&self, message: M) -> Result<(), Disconnected> {
+        let message = message.into();
+        let frame = if self.encoded {
+            Frame::Text(encode_message(&message))
+        } else {
+            Frame::Message(message)
+        };
+        self.sender.unbounded_send(frame).map_err(|_| Disconnected)
+    }
+
+    /// Sends raw JSON text to the other endpoint, e.g. a malformed payload
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The text to send, parsed on receipt
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once queued, or `Disconnected` if the other endpoint is dropped
+    pub fn send_raw<S: Into<String>>(&self, payload: S) -> Result<(), Disconnected> {
+        self.sender
+            .unbounded_send(Frame::Text(payload.into()))
+            .map_err(|_| Disconnected)
+    }
+
+    /// Receives the next payload, waiting for one to be sent
+    ///
+    /// # Returns
+    ///
+    /// The payload, or why it failed to parse; None once the other endpoint is
+    /// dropped and every payload it sent is received
+    pub async fn recv(&mut self) -> Option<Result<Message, ParseError>> {
+        self.receiver.next().await.map(Frame::into_message)
+    }
+
+    /// Receives the next payload if one is queued, without waiting
+    ///
+    /// # Returns
+    ///
+    /// The payload, or why it failed to parse; None if no payload is queued
+    pub fn try_recv(&mut self) -> Option<Result<Message, ParseError>> {
+        self.receiver
+            .next()
+            .now_or_never()
+            .flatten()
+            .map(Frame::into_message)
+    }
+
+    /// Answers the payloads received with a router, until the other endpoint is dropped
+    ///
+    /// Payloads that fail to parse are answered with the matching error response.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The router dispatching the payloads received
+    pub async fn serve(&mut self, router: &AsyncRouter) {
+        while let Some(received) = self.recv().await {
+            let response = match received {
+                Ok(message) => router.handle_message(message).await,
+                Err(err) => Some(Message::Single(err.into_response())),
+            };
+            if let Some(response) = response {
+                if self.send(response).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Checks whether payloads are exchanged as serialized JSON text
+    ///
+    /// # Returns
+    ///
+    /// `true` for endpoints created by `pair_encoded`
+    pub fn is_encoded(&self) -> bool {
+        self.encoded
+    }
+}
+
+impl Stream for Endpoint {
+    type Item = Result<Message, ParseError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.receiver
+            .poll_next_unpin(cx)
+            .map(|frame| frame.map(Frame::into_message))
+    }
+}
+
+/// The error returned when sending to an endpoint that is dropped
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+    /// Formats the error as a string
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter to write to
+    ///
+    /// # Returns
+    ///
+    /// A `fmt::Result` indicating success or failure of the formatting operation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the other endpoint is disconnected")
+    }
+}
+
+impl error::Error for Disconnected {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, Params};
+    use futures::executor::block_on;
+    use futures::future;
+    use serde_json::json;
+
+    #[test]
+    fn exchanges_messages() {
+        for (mut left, mut right) in [pair(), pair_encoded()] {
+            left.send(JsonRpc::notification("a")).unwrap();
+            left.send(vec![JsonRpc::request(1, "b")]).unwrap();
+            right.send(JsonRpc::success(1, &json!(true))).unwrap();
+
+            assert_eq!(
+                right.try_recv(),
+                Some(Ok(Message::Single(JsonRpc::notification("a"))))
+            );
+            assert_eq!(
+                right.try_recv(),
+                Some(Ok(Message::from(vec![JsonRpc::request(1, "b")])))
+            );
+            assert_eq!(right.try_recv(), None);
+            assert_eq!(
+                block_on(left.recv()),
+                Some(Ok(Message::Single(JsonRpc::success(1, &json!(true)))))
+            );
+
+            drop(right);
+            assert_eq!(left.send(JsonRpc::notification("c")), Err(Disconnected));
+            assert_eq!(block_on(left.recv()), None);
+        }
+    }
+
+    #[test]
+    fn encoded_pair_parses_payloads() {
+        let (left, mut right) = pair_encoded();
+        assert!(left.is_encoded());
+        left.send_raw(r#"{"jsonrpc":"2.0","method":1,"id":7}"#)
+            .unwrap();
+
+        let err = right.try_recv().unwrap().unwrap_err();
+        assert_eq!(err.into_response().get_id(), Some(crate::Id::from(7)));
+    }
+
+    #[test]
+    fn client_and_router() {
+        let mut router = AsyncRouter::new();
+        router.add_method("double", |params: Params| async move {
+            let (n,): (i64,) = params.parse()?;
+            Ok(n * 2)
+        });
+
+        let (client_end, mut server_end) = pair_encoded();
+        let client = Client::new();
+        let (request, response) = client.request_with_params("double", json!([21])).unwrap();
+        client_end.send(request).unwrap();
+        client_end.send_raw("{").unwrap();
+        let mut client_end = client_end;
+
+        let client = &client;
+        block_on(future::join(server_end.serve(&router), async move {
+            let message = client_end.recv().await.unwrap().unwrap();
+            assert!(client.handle_message(message).is_empty());
+            let parse_error = client_end.recv().await.unwrap().unwrap();
+            assert!(matches!(
+                parse_error,
+                Message::Single(JsonRpc::Error(ref error)) if error.error.code == -32700
+            ));
+        }));
+        assert_eq!(block_on(response), Ok(json!(42)));
+    }
+}
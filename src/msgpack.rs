@@ -0,0 +1,143 @@
+//! MessagePack encoding of JSON-RPC 2.0 messages
+//!
+//! Messages are encoded as MessagePack maps keyed by member name, mirroring
+//! their JSON form, rather than as the positional arrays of msgpack-rpc. They
+//! are decoded with the same variant detection as JSON, and binary or
+//! extension values, which have no JSON equivalent, are rejected.
+
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::binary::{parse_decoded, Portable};
+use crate::error::ParseError;
+use crate::jsonrpc::Message;
+
+/// The error returned when encoding MessagePack fails
+pub type EncodeError = rmp_serde::encode::Error;
+
+/// The error returned when decoding MessagePack fails
+pub type DecodeError = rmp_serde::decode::Error;
+
+/// Encodes a value as MessagePack, with structs as maps keyed by member name
+///
+/// # Arguments
+///
+/// * `value` - The value to encode, e.g. a `JsonRpc`, `Params`, `Id` or `Error`
+///
+/// # Returns
+///
+/// The MessagePack bytes, or the error encountered
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::{msgpack, JsonRpc};
+///
+/// let request = JsonRpc::request(1, "ping");
+/// let bytes = msgpack::to_vec(&request).unwrap();
+/// assert_eq!(msgpack::from_slice::<JsonRpc>(&bytes).unwrap(), request);
+/// ```
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    rmp_serde::to_vec_named(&Portable(value))
+}
+
+/// Encodes a value as MessagePack into a writer, with structs as maps keyed by member name
+///
+/// # Arguments
+///
+/// * `value` - The value to encode
+/// * `writer` - The stream to write the MessagePack bytes to
+///
+/// # Returns
+///
+/// `Ok(())` once written, or the error encountered
+pub fn to_writer<T: Serialize + ?Sized, W: Write>(
+    value: &T,
+    mut writer: W,
+) -> Result<(), EncodeError> {
+    rmp_serde::encode::write_named(&mut writer, &Portable(value))
+}
+
+/// Decodes a value from MessagePack bytes
+///
+/// # Arguments
+///
+/// * `bytes` - The MessagePack bytes to decode
+///
+/// # Returns
+///
+/// The decoded value, or the error encountered
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    rmp_serde::from_slice(bytes)
+}
+
+/// Decodes a value from a stream of MessagePack bytes
+///
+/// # Arguments
+///
+/// * `reader` - The stream to read the MessagePack bytes from
+///
+/// # Returns
+///
+/// The decoded value, or the error encountered
+pub fn from_reader<T: DeserializeOwned, R: Read>(reader: R) -> Result<T, DecodeError> {
+    rmp_serde::from_read(reader)
+}
+
+/// Decodes a single message or a batch from MessagePack bytes, mapping failures to spec errors
+///
+/// Undecodable bytes yield a parse error (-32700), and a value that is not a
+/// valid payload yields an invalid request error (-32600), as with
+/// [`JsonRpc::parse_message`](crate::JsonRpc::parse_message).
+///
+/// # Arguments
+///
+/// * `bytes` - The MessagePack bytes to decode
+///
+/// # Returns
+///
+/// A Result containing either the decoded Message or a ParseError
+pub fn parse_message(bytes: &[u8]) -> Result<Message, ParseError> {
+    parse_decoded(rmp_serde::from_slice(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::tests::{self, Encoding};
+
+    struct MsgPack;
+
+    impl Encoding for MsgPack {
+        fn to_vec<T: Serialize>(value: &T) -> Vec<u8> {
+            to_vec(value).expect("Unable to encode value")
+        }
+
+        fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> T {
+            from_slice(bytes).expect("Unable to decode value")
+        }
+
+        fn parse_message(bytes: &[u8]) -> Result<Message, ParseError> {
+            parse_message(bytes)
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        tests::round_trips::<MsgPack>();
+    }
+
+    #[test]
+    fn parses_messages() {
+        tests::parses_messages::<MsgPack>();
+
+        // A binary value has no JSON equivalent
+        assert_eq!(
+            parse_message(&[0xc4, 0x01, 0x00]).unwrap_err().code(),
+            -32700
+        );
+        assert_eq!(parse_message(&[0xc1]).unwrap_err().code(), -32700);
+    }
+}
@@ -0,0 +1,619 @@
+//! JSON-RPC 2.0 method routing
+//!
+//! This module provides a `Router` that dispatches requests and notifications
+//! to handlers registered by method name, and wraps what they return into the
+//! matching response object. `AsyncRouter`, behind the `async` feature, does
+//! the same for asynchronous handlers and runs the entries of a batch concurrently.
+
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
+
+#[cfg(feature = "async")]
+use futures_util::future::{self, BoxFuture, FutureExt};
+#[cfg(feature = "async")]
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+
+#[cfg(feature = "async")]
+use crate::jsonrpc::Batch;
+use crate::jsonrpc::{encode_message, Id, JsonRpc, Message, Params, Success};
+use crate::{Error as RpcError, Result};
+
+/// A registered method handler
+type Method = Box<dyn Fn(Params) -> Result<Value> + Send + Sync>;
+
+/// A registered asynchronous method handler
+#[cfg(feature = "async")]
+type AsyncMethod = Box<dyn Fn(Params) -> BoxFuture<'static, Result<Value>> + Send + Sync>;
+
+/// The number of batch entries an `AsyncRouter` runs at once by default
+#[cfg(feature = "async")]
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+/// Dispatches JSON-RPC messages to handlers registered by method name
+///
+/// A request is answered with a Success carrying the value returned by its
+/// handler, or an Error carrying the returned error, echoing the request `id`.
+/// Unknown methods are answered with a method not found error (-32601).
+/// Notifications run their handler but are never answered.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::{Error, JsonRpc, Params, Router};
+/// use serde_json::json;
+///
+/// let mut router = Router::new();
+/// router.add_method("sum", |params: Params| match params {
+///     Params::Array(values) => Ok(values.iter().filter_map(|v| v.as_i64()).sum::<i64>()),
+///     _ => Err(Error::invalid_params()),
+/// });
+///
+/// let response = router.handle(JsonRpc::request_with_params(1, "sum", json!([1, 2, 4])));
+/// assert_eq!(response, Some(JsonRpc::success(1, &json!(7))));
+///
+/// let response = router.handle(JsonRpc::request(2, "missing"));
+/// assert_eq!(response, Some(JsonRpc::error(2, Error::method_not_found())));
+/// ```
+#[derive(Default)]
+pub struct Router {
+    methods: HashMap<String, Method>,
+}
+
+impl Router {
+    /// Creates a router with no registered methods
+    ///
+    /// # Returns
+    ///
+    /// A new empty `Router`
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    /// Registers the handler of a method
+    ///
+    /// The value returned by the handler is serialized into the `result` of the
+    /// response; a value that fails to serialize is answered with an internal
+    /// error (-32603). Registering a method again replaces its handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the method
+    /// * `handler` - Called with the params of the method, or `Params::None` when omitted
+    ///
+    /// # Returns
+    ///
+    /// The router, for chaining registrations
+    pub fn add_method<F, R>(&mut self, name: &str, handler: F) -> &mut Self
+    where
+        F: Fn(Params) -> Result<R> + Send + Sync + 'static,
+        R: Serialize,
+    {
+        self.methods.insert(
+            String::from(name),
+            Box::new(move |params| handler(params).and_then(|result| to_result(&result))),
+        );
+        self
+    }
+
+    /// Checks whether a method is registered
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the method
+    ///
+    /// # Returns
+    ///
+    /// `true` if a handler is registered for the method
+    pub fn has_method(&self, name: &str) -> bool {
+        self.methods.contains_key(name)
+    }
+
+    /// Invokes the handler of a method
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method
+    /// * `params` - The params to pass to the handler
+    ///
+    /// # Returns
+    ///
+    /// The result of the handler, or a method not found error if no handler is registered
+    pub fn call(&self, method: &str, params: Params) -> Result<Value> {
+        match self.methods.get(method) {
+            Some(handler) => handler(params),
+            None => Err(RpcError::method_not_found()),
+        }
+    }
+
+    /// Dispatches a single JSON-RPC message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to dispatch
+    ///
+    /// # Returns
+    ///
+    /// The response to a request, or None for notifications and responses
+    pub fn handle(&self, message: JsonRpc) -> Option<JsonRpc> {
+        match message {
+            JsonRpc::Request(request) => {
+                let result = self.call(&request.method, params_or_none(request.params));
+                Some(respond(request.id, result))
+            }
+            JsonRpc::Notification(notification) => {
+                let _ = self.call(&notification.method, params_or_none(notification.params));
+                None
+            }
+            JsonRpc::Success(_) | JsonRpc::Error(_) => None,
+        }
+    }
+
+    /// Dispatches a single message or a batch
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The payload to dispatch
+    ///
+    /// # Returns
+    ///
+    /// The response payload, or None when there is nothing to send back
+    pub fn handle_message(&self, message: Message) -> Option<Message> {
+        message.respond(|message| self.handle(message))
+    }
+
+    /// Parses and dispatches a JSON payload, serializing the response
+    ///
+    /// Payloads that fail to parse are answered with the matching parse error
+    /// or invalid request error.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string holding a single message or a batch
+    ///
+    /// # Returns
+    ///
+    /// The serialized response payload, or None when there is nothing to send back
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_lite::Router;
+    ///
+    /// let router = Router::new();
+    /// let response = router.handle_str(r#"{"jsonrpc":"2.0","method":"foo","params":"bar","id":1}"#);
+    /// assert!(response.unwrap().contains("-32600"));
+    /// ```
+    pub fn handle_str(&self, input: &str) -> Option<String> {
+        let response = match JsonRpc::parse_message(input) {
+            Ok(message) => self.handle_message(message)?,
+            Err(err) => Message::Single(err.into_response()),
+        };
+        Some(encode_message(&response))
+    }
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Dispatches JSON-RPC messages to asynchronous handlers registered by method name
+///
+/// Responses are built as with [`Router`]. The entries of a batch run
+/// concurrently, up to the concurrency limit, and their responses are
+/// assembled in the order of the entries. Handlers are plain futures, so
+/// the router works with any executor.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::{AsyncRouter, JsonRpc};
+/// use serde_json::json;
+///
+/// let mut router = AsyncRouter::new();
+/// router.add_method("ping", |_| async { Ok("pong") });
+///
+/// let response = futures::executor::block_on(router.handle(JsonRpc::request(1, "ping")));
+/// assert_eq!(response, Some(JsonRpc::success(1, &json!("pong"))));
+/// ```
+#[cfg(feature = "async")]
+pub struct AsyncRouter {
+    methods: HashMap<String, AsyncMethod>,
+    concurrency: usize,
+}
+
+#[cfg(feature = "async")]
+impl Default for AsyncRouter {
+    fn default() -> Self {
+        AsyncRouter {
+            methods: HashMap::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncRouter {
+    /// Creates a router with no registered methods
+    ///
+    /// # Returns
+    ///
+    /// A new empty `AsyncRouter` running up to `DEFAULT_CONCURRENCY` batch entries at once
+    pub fn new() -> Self {
+        AsyncRouter::default()
+    }
+
+    /// Sets how many entries of a batch run at once
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of concurrent entries; 0 is treated as 1
+    ///
+    /// # Returns
+    ///
+    /// The router, for chaining
+    pub fn set_concurrency(&mut self, limit: usize) -> &mut Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Gets how many entries of a batch run at once
+    ///
+    /// # Returns
+    ///
+    /// The maximum number of concurrent entries
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Registers the asynchronous handler of a method
+    ///
+    /// The value the handler resolves to is serialized into the `result` of the
+    /// response; a value that fails to serialize is answered with an internal
+    /// error (-32603). Registering a method again replaces its handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the method
+    /// * `handler` - Called with the params of the method, or `Params::None` when omitted
+    ///
+    /// # Returns
+    ///
+    /// The router, for chaining registrations
+    pub fn add_method<F, Fut, R>(&mut self, name: &str, handler: F) -> &mut Self
+    where
+        F: Fn(Params) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+        R: Serialize,
+    {
+        self.methods.insert(
+            String::from(name),
+            Box::new(move |params| {
+                handler(params)
+                    .map(|result| result.and_then(|result| to_result(&result)))
+                    .boxed()
+            }),
+        );
+        self
+    }
+
+    /// Checks whether a method is registered
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the method
+    ///
+    /// # Returns
+    ///
+    /// `true` if a handler is registered for the method
+    pub fn has_method(&self, name: &str) -> bool {
+        self.methods.contains_key(name)
+    }
+
+    /// Invokes the handler of a method
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method
+    /// * `params` - The params to pass to the handler
+    ///
+    /// # Returns
+    ///
+    /// The result of the handler, or a method not found error if no handler is registered
+    pub async fn call(&self, method: &str, params: Params) -> Result<Value> {
+        match self.methods.get(method) {
+            Some(handler) => handler(params).await,
+            None => Err(RpcError::method_not_found()),
+        }
+    }
+
+    /// Dispatches a single JSON-RPC message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to dispatch
+    ///
+    /// # Returns
+    ///
+    /// The response to a request, or None for notifications and responses
+    pub async fn handle(&self, message: JsonRpc) -> Option<JsonRpc> {
+        match message {
+            JsonRpc::Request(request) => {
+                let result = self
+                    .call(&request.method, params_or_none(request.params))
+                    .await;
+                Some(respond(request.id, result))
+            }
+            JsonRpc::Notification(notification) => {
+                let _ = self
+                    .call(&notification.method, params_or_none(notification.params))
+                    .await;
+                None
+            }
+            JsonRpc::Success(_) | JsonRpc::Error(_) => None,
+        }
+    }
+
+    /// Dispatches the entries of a batch concurrently
+    ///
+    /// Malformed entries are answered with their error response and
+    /// notifications are not answered, as with [`Batch::respond`].
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The batch to dispatch, e.g. a `Vec<JsonRpc>` from `JsonRpc::parse_vec`
+    ///
+    /// # Returns
+    ///
+    /// The responses in the order of the entries, or None when there is nothing to send back
+    pub async fn handle_batch<B: Into<Batch>>(&self, batch: B) -> Option<Vec<JsonRpc>> {
+        let responses: Vec<JsonRpc> = stream::iter(batch.into())
+            .map(|entry| async move {
+                match entry {
+                    Ok(message) => self.handle(message).await,
+                    Err(err) => Some(err.into_response()),
+                }
+            })
+            .buffered(self.concurrency)
+            .filter_map(future::ready)
+            .collect()
+            .await;
+
+        if responses.is_empty() {
+            None
+        } else {
+            Some(responses)
+        }
+    }
+
+    /// Dispatches a single message or a batch
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The payload to dispatch
+    ///
+    /// # Returns
+    ///
+    /// The response payload, or None when there is nothing to send back
+    pub async fn handle_message(&self, message: Message) -> Option<Message> {
+        match message {
+            Message::Single(message) => self.handle(message).await.map(Message::Single),
+            Message::Batch(batch) => self
+                .handle_batch(batch)
+                .await
+                .map(|responses| Message::Batch(responses.into())),
+        }
+    }
+
+    /// Parses and dispatches a JSON payload, serializing the response
+    ///
+    /// Payloads that fail to parse are answered with the matching parse error
+    /// or invalid request error.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON string holding a single message or a batch
+    ///
+    /// # Returns
+    ///
+    /// The serialized response payload, or None when there is nothing to send back
+    pub async fn handle_str(&self, input: &str) -> Option<String> {
+        let response = match JsonRpc::parse_message(input) {
+            Ok(message) => self.handle_message(message).await?,
+            Err(err) => Message::Single(err.into_response()),
+        };
+        Some(encode_message(&response))
+    }
+}
+
+#[cfg(feature = "async")]
+impl fmt::Debug for AsyncRouter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncRouter")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
+
+/// Serializes the value returned by a handler into a `result`
+pub(crate) fn to_result<R: Serialize>(result: &R) -> Result<Value> {
+    serde_json::to_value(result)
+        .map_err(|err| RpcError::internal_error().with_data(err.to_string()))
+}
+
+/// Passes omitted params to handlers as `Params::None`
+pub(crate) fn params_or_none(params: Option<Params>) -> Params {
+    params.unwrap_or(Params::None(()))
+}
+
+/// Wraps the outcome of a handler into the response to a request
+pub(crate) fn respond(id: Id, result: Result<Value>) -> JsonRpc {
+    match result {
+        Ok(result) => JsonRpc::Success(Success::new(id, result)),
+        Err(error) => JsonRpc::error(id, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn notifications_are_not_answered() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut router = Router::new();
+        router.add_method("log", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+
+        assert_eq!(router.handle(JsonRpc::notification("log")), None);
+        assert_eq!(router.handle(JsonRpc::notification("missing")), None);
+        assert_eq!(router.handle(JsonRpc::success(1, &json!(1))), None);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handle_str_batch() {
+        let mut router = Router::new();
+        router
+            .add_method("echo", |params| Ok(Value::from(params)))
+            .add_method("fail", |_| Err::<(), _>(RpcError::invalid_params()));
+
+        let response = router.handle_str(
+            r#"[
+                {"jsonrpc":"2.0","method":"echo","params":[1],"id":1},
+                {"jsonrpc":"2.0","method":"echo","params":[2]},
+                {"jsonrpc":"2.0","method":"fail","id":2},
+                {"jsonrpc":"2.0","method":"nope","id":3}
+            ]"#,
+        );
+        let response: Value = serde_json::from_str(&response.expect("Batch response is empty"))
+            .expect("Unable to parse batch response");
+        assert_eq!(
+            response,
+            json!([
+                {"jsonrpc": "2.0", "result": [1], "id": 1},
+                {"jsonrpc": "2.0", "error": {"code": -32602, "message": "Invalid params"}, "id": 2},
+                {"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 3}
+            ])
+        );
+
+        let notifications =
+            r#"[{"jsonrpc":"2.0","method":"echo"},{"jsonrpc":"2.0","method":"echo"}]"#;
+        assert_eq!(router.handle_str(notifications), None);
+        assert!(router
+            .handle_str("[")
+            .expect("Parse error is not answered")
+            .contains("-32700"));
+    }
+
+    /// Yields to the executor once before completing
+    #[cfg(feature = "async")]
+    struct YieldNow(bool);
+
+    #[cfg(feature = "async")]
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<()> {
+            if self.0 {
+                std::task::Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_batch_runs_concurrently_in_order() {
+        use futures::channel::oneshot;
+        use std::sync::Mutex;
+
+        let (sender, receiver) = oneshot::channel::<i64>();
+        let receiver = Arc::new(Mutex::new(Some(receiver)));
+        let sender = Arc::new(Mutex::new(Some(sender)));
+
+        let mut router = AsyncRouter::new();
+        router
+            .add_method("wait", move |_| {
+                let receiver = receiver.lock().unwrap().take();
+                async move {
+                    match receiver {
+                        Some(receiver) => receiver.await.map_err(|_| RpcError::internal_error()),
+                        None => Err(RpcError::internal_error()),
+                    }
+                }
+            })
+            .add_method("release", move |_| {
+                let sender = sender.lock().unwrap().take();
+                async move {
+                    sender.map(|sender| sender.send(42));
+                    Ok(true)
+                }
+            });
+
+        let batch = JsonRpc::parse_vec(
+            r#"[
+                {"jsonrpc":"2.0","method":"wait","id":1},
+                {"jsonrpc":"2.0","method":"release"},
+                {"jsonrpc":"2.0","method":"release","id":2}
+            ]"#,
+        )
+        .expect("Unable to parse batch");
+        let responses = futures::executor::block_on(router.handle_batch(batch));
+        assert_eq!(
+            responses,
+            Some(vec![
+                JsonRpc::success(1, &json!(42)),
+                JsonRpc::success(2, &json!(true)),
+            ])
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_batch_respects_concurrency() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (counter, max) = (in_flight.clone(), peak.clone());
+
+        let mut router = AsyncRouter::new();
+        router.set_concurrency(2).add_method("work", move |_| {
+            let (counter, max) = (counter.clone(), max.clone());
+            async move {
+                let current = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(current, Ordering::SeqCst);
+                YieldNow(false).await;
+                counter.fetch_sub(1, Ordering::SeqCst);
+                Ok(current)
+            }
+        });
+
+        let batch: Vec<_> = (0..5).map(|id| JsonRpc::request(id, "work")).collect();
+        let responses = futures::executor::block_on(router.handle_batch(batch))
+            .expect("Batch response is empty");
+        let ids: Vec<_> = responses.iter().map(JsonRpc::get_id).collect();
+        assert_eq!(ids, (0..5).map(|id| Some(Id::from(id))).collect::<Vec<_>>());
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,239 @@
+//! Blocking JSON-RPC 2.0 servers over byte streams
+//!
+//! This module runs a [`Router`] over a pair of blocking streams: framed
+//! messages are read one at a time, dispatched, and their responses written
+//! back. `serve_stdio` does so over the process' standard input and output,
+//! which is how editors drive language servers and similar tools.
+
+use std::io::{self, BufRead, Write};
+
+use crate::framing::{FrameError, Framing};
+use crate::jsonrpc::{JsonRpc, Message};
+use crate::router::Router;
+use crate::Error as RpcError;
+
+/// The notification that stops a server, as in the Language Server Protocol
+pub const EXIT_METHOD: &str = "exit";
+
+/// Serves a router over standard input and output with `Content-Length` framing
+///
+/// See `serve` for the details.
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching incoming messages
+///
+/// # Returns
+///
+/// `Ok(())` once standard input is closed or an `exit` notification is
+/// received, or the error that stopped the server
+pub fn serve_stdio(router: &Router) -> Result<(), FrameError> {
+    serve(
+        router,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        Framing::default(),
+    )
+}
+
+/// Serves a router over a pair of blocking streams
+///
+/// Messages are read and dispatched one at a time, and responses are written
+/// as soon as they are ready. Payloads that fail to parse, and frames that are
+/// too large, are answered with the matching error response and skipped.
+///
+/// The server stops at the end of the input, or after dispatching an `exit`
+/// notification, which runs its handler if one is registered.
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching incoming messages
+/// * `input` - The stream to read requests from
+/// * `output` - The stream to write responses to
+/// * `framing` - The framing of messages on both streams, e.g. a `LineCodec`
+///
+/// # Returns
+///
+/// `Ok(())` once the input is closed or an `exit` notification is received,
+/// or the error that stopped the server
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::framing::LineCodec;
+/// use jsonrpc_lite::server::serve;
+/// use jsonrpc_lite::{Params, Router};
+///
+/// let mut router = Router::new();
+/// router.add_method("ping", |_: Params| Ok("pong"));
+///
+/// let input = br#"{"jsonrpc":"2.0","method":"ping","id":1}
+/// {"jsonrpc":"2.0","method":"exit"}
+/// {"jsonrpc":"2.0","method":"ping","id":2}
+/// "#;
+/// let mut output = Vec::new();
+/// serve(&router, &input[..], &mut output, LineCodec::new()).unwrap();
+/// assert_eq!(output, b"{\"jsonrpc\":\"2.0\",\"result\":\"pong\",\"id\":1}\n");
+/// ```
+pub fn serve<R, W, F>(router: &Router, input: R, output: W, framing: F) -> Result<(), FrameError>
+where
+    R: BufRead,
+    W: Write,
+    F: Into<Framing>,
+{
+    match framing.into() {
+        Framing::Line(codec) => {
+            let mut writer = codec.writer(output);
+            run(router, codec.reader(input), |message| {
+                writer.write_message(message)
+            })
+        }
+        Framing::Header(codec) => {
+            let mut writer = codec.clone().writer(output);
+            run(router, codec.reader(input), |message| {
+                writer.write_message(message)
+            })
+        }
+        Framing::LengthPrefixed(codec) => {
+            let mut writer = codec.writer(output);
+            run(router, codec.reader(input), |message| {
+                writer.write_message(message)
+            })
+        }
+    }
+}
+
+/// Dispatches the messages read until the end of the input or an exit notification
+fn run<I, F>(router: &Router, messages: I, mut write: F) -> Result<(), FrameError>
+where
+    I: Iterator<Item = Result<Message, FrameError>>,
+    F: FnMut(&Message) -> Result<(), FrameError>,
+{
+    for message in messages {
+        let message = match message {
+            Ok(message) => message,
+            Err(FrameError::Parse(err)) => {
+                write(&Message::Single(err.into_response()))?;
+                continue;
+            }
+            Err(err @ FrameError::TooLarge { .. }) => {
+                let error = RpcError::invalid_request().with_data(err.to_string());
+                write(&Message::Single(JsonRpc::error((), error)))?;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let exit = is_exit(&message);
+        if let Some(response) = router.handle_message(message) {
+            write(&response)?;
+        }
+        if exit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether a message is an `exit` notification
+fn is_exit(message: &Message) -> bool {
+    match *message {
+        Message::Single(JsonRpc::Notification(ref notification)) => {
+            notification.method == EXIT_METHOD
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{HeaderCodec, LineCodec};
+    use crate::Params;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router.add_method("echo", |params: Params| Ok(Value::from(params)));
+        router
+    }
+
+    fn lines(output: Vec<u8>) -> Vec<Value> {
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn answers_until_eof() {
+        let input = b"{\"jsonrpc\":\"2.0\",\"method\":\"echo\",\"params\":[1],\"id\":1}\n\
+                      {\"jsonrpc\":\"2.0\",\"method\":\"echo\"}\n\
+                      {\"jsonrpc\":\"2.0\",\"method\":\"echo\",\n\
+                      {\"jsonrpc\":\"2.0\",\"method\":\"echo\",\"params\":[\"too long\"],\"id\":3}\n\
+                      [{\"jsonrpc\":\"2.0\",\"method\":\"missing\",\"id\":\"a\"}]\n\
+                      {\"jsonrpc\":\"2.0\",\"method\":\"echo\",\"params\":[2],\"id\":2}\n";
+        let mut output = Vec::new();
+        serve(
+            &router(),
+            &input[..],
+            &mut output,
+            LineCodec::with_max_length(60),
+        )
+        .expect("Unable to serve");
+
+        assert_eq!(
+            lines(output),
+            vec![
+                json!({"jsonrpc": "2.0", "result": [1], "id": 1}),
+                json!({"jsonrpc": "2.0", "error": {
+                    "code": -32700,
+                    "message": "Parse error",
+                    "data": "EOF while parsing a value at line 1 column 33",
+                }, "id": null}),
+                json!({"jsonrpc": "2.0", "error": {
+                    "code": -32600,
+                    "message": "Invalid request",
+                    "data": "frame exceeds the maximum length of 60 bytes",
+                }, "id": null}),
+                json!([{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": "a"}]),
+                json!({"jsonrpc": "2.0", "result": [2], "id": 2}),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_on_exit() {
+        let exited = Arc::new(AtomicBool::new(false));
+        let mut router = router();
+        let flag = exited.clone();
+        router.add_method(EXIT_METHOD, move |_: Params| {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+
+        let mut input = Vec::new();
+        let mut writer = HeaderCodec::new().writer(&mut input);
+        writer.write_message(&JsonRpc::request(1, "echo")).unwrap();
+        writer
+            .write_message(&JsonRpc::notification("exit"))
+            .unwrap();
+        writer.write_message(&JsonRpc::request(2, "echo")).unwrap();
+
+        let mut output = Vec::new();
+        serve(&router, &input[..], &mut output, Framing::default()).expect("Unable to serve");
+        assert!(exited.load(Ordering::SeqCst));
+
+        let responses: Vec<_> = HeaderCodec::new()
+            .reader(&output[..])
+            .collect::<Result<_, _>>()
+            .expect("Unable to read responses");
+        assert_eq!(
+            responses,
+            vec![Message::Single(JsonRpc::success(1, &Value::Null))]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Parsing JSON-RPC 2.0 messages with simd-json
+//!
+//! These functions mirror [`JsonRpc::parse`], [`JsonRpc::parse_vec`] and
+//! [`JsonRpc::parse_message`] and produce the same types, but parse with
+//! simd-json, which uses SIMD instructions where the CPU supports them.
+//!
+//! simd-json parses in place: strings are unescaped inside the buffer, so the
+//! input must be mutable and its content is unspecified once parsed. Copy the
+//! payload first if it is still needed afterwards.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::ParseError;
+use crate::jsonrpc::{JsonRpc, Message, ParseOptions};
+use crate::Error as RpcError;
+
+/// The error returned when parsing with simd-json fails
+pub type SimdError = simd_json::Error;
+
+/// Parses a JSON buffer into a JSON-RPC message
+///
+/// # Arguments
+///
+/// * `input` - The JSON buffer to parse, modified while parsing
+///
+/// # Returns
+///
+/// A Result containing either the parsed JsonRpc or a simd-json error
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_lite::{simd, JsonRpc};
+///
+/// let mut input = br#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}"#.to_vec();
+/// let request = simd::parse(&mut input).unwrap();
+/// assert_eq!(request.get_method(), Some("subtract"));
+/// ```
+pub fn parse(input: &mut [u8]) -> Result<JsonRpc, SimdError> {
+    simd_json::serde::from_slice(input)
+}
+
+/// Parses a JSON buffer into a vector of JSON-RPC messages
+///
+/// # Arguments
+///
+/// * `input` - The JSON buffer to parse, modified while parsing
+///
+/// # Returns
+///
+/// A Result containing either a vector of parsed JsonRpc objects or a simd-json error
+pub fn parse_vec(input: &mut [u8]) -> Result<Vec<JsonRpc>, SimdError> {
+    simd_json::serde::from_slice(input)
+}
+
+/// Parses a JSON buffer holding either a single message or a batch, mapping
+/// failures to spec errors
+///
+/// Malformed JSON yields a parse error (-32700), and valid JSON that is not a
+/// valid payload yields an invalid request error (-32600), as with
+/// [`JsonRpc::parse_message`]. The shape is decided by the first
+/// non-whitespace byte, `[` starting a batch. Messages are deserialized
+/// straight from the simd-json tape; a batch is only read again entry by entry
+/// when some of its entries are malformed.
+///
+/// # Arguments
+///
+/// * `input` - The JSON buffer to parse, modified while parsing
+///
+/// # Returns
+///
+/// A Result containing either the parsed Message or a ParseError
+pub fn parse_message(input: &mut [u8]) -> Result<Message, ParseError> {
+    let is_batch = input.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[');
+    let mut deserializer = simd_json::Deserializer::from_slice(input)
+        .map_err(|err| ParseError::new(RpcError::parse_error().with_data(err.to_string()), None))?;
+    let parsed = if is_batch {
+        Vec::<JsonRpc>::deserialize(&mut deserializer).map(Message::from)
+    } else {
+        JsonRpc::deserialize(&mut deserializer).map(Message::Single)
+    };
+    match parsed {
+        Ok(Message::Batch(batch)) if batch.is_empty() => Err(ParseError::new(
+            RpcError::invalid_request().with_data("empty batch"),
+            None,
+        )),
+        Ok(message) => Ok(message),
+        // The tape is still valid JSON: read it again as a value to keep the
+        // valid batch entries and recover the ids of the malformed ones
+        Err(_) => {
+            deserializer.restart();
+            let value = Value::deserialize(&mut deserializer).map_err(|err| {
+                ParseError::new(RpcError::parse_error().with_data(err.to_string()), None)
+            })?;
+            Message::from_json(value, &ParseOptions::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Id;
+
+    const INPUTS: &[&str] = &[
+        r#"{"jsonrpc":"2.0","method":"subtract","params":[42,-23.5],"id":1}"#,
+        r#"{"jsonrpc":"2.0","method":"update","params":{"a":"\"b\""}}"#,
+        r#"{"jsonrpc":"2.0","result":{"c":[null,true]},"id":"x-1"}"#,
+        r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found","data":1},"id":null}"#,
+    ];
+
+    #[test]
+    fn matches_serde_json() {
+        for input in INPUTS {
+            let expected = JsonRpc::parse(input).unwrap();
+            assert_eq!(parse(&mut input.as_bytes().to_vec()).unwrap(), expected);
+        }
+
+        let batch = format!("[{}]", INPUTS.join(","));
+        let expected = JsonRpc::parse_vec(&batch).unwrap();
+        assert_eq!(
+            parse_vec(&mut batch.clone().into_bytes()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_message(&mut batch.into_bytes()),
+            Ok(Message::from(expected))
+        );
+
+        assert!(parse(&mut b"{\"jsonrpc\":\"2.0\"".to_vec()).is_err());
+        assert!(parse(&mut b"{\"jsonrpc\":\"2.0\",\"method\":1}".to_vec()).is_err());
+    }
+
+    #[test]
+    fn maps_failures() {
+        let mut invalid = br#"{"jsonrpc":"2.0","method":1,"id":4}"#.to_vec();
+        let err = parse_message(&mut invalid).unwrap_err();
+        assert_eq!((err.code(), err.id), (-32600, Some(Id::from(4))));
+
+        let err = parse_message(&mut b"[1,".to_vec()).unwrap_err();
+        assert_eq!(err.code(), -32700);
+
+        let err = parse_message(&mut b" []".to_vec()).unwrap_err();
+        assert_eq!(err.code(), -32600);
+
+        let err = parse_message(&mut b"1".to_vec()).unwrap_err();
+        assert_eq!(err.code(), -32600);
+
+        let mut mixed =
+            format!(r#" [{},{{"jsonrpc":"2.0","method":1,"id":5}}]"#, INPUTS[0]).into_bytes();
+        let batch = match parse_message(&mut mixed) {
+            Ok(Message::Batch(batch)) => batch,
+            other => panic!("expected a batch, got {:?}", other),
+        };
+        assert_eq!(batch.len(), 2);
+        assert_eq!(
+            batch.messages().collect::<Vec<_>>(),
+            vec![&JsonRpc::parse(INPUTS[0]).unwrap()]
+        );
+        let err = batch.iter().find_map(|entry| entry.as_ref().err()).unwrap();
+        assert_eq!((err.code(), err.id.clone()), (-32600, Some(Id::from(5))));
+    }
+}
@@ -0,0 +1,323 @@
+//! Bidirectional JSON-RPC 2.0 over WebSocket
+//!
+//! This module connects both sides of a WebSocket session: a `Peer` sends
+//! requests and notifications and awaits responses by `id`, while the incoming
+//! requests and notifications are dispatched to an [`AsyncRouter`]. Either end
+//! of the socket can do both.
+//!
+//! Messages are carried as text frames. The socket can be any stream and sink
+//! of `tungstenite` messages, e.g. a `tokio_tungstenite::WebSocketStream`; the
+//! `Connection` future drives it and must be polled, e.g. by spawning it.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_util::future::{self, BoxFuture, Either, FutureExt};
+use futures_util::{Sink, Stream, StreamExt, TryStreamExt};
+use tungstenite::{Error as WsError, Message as WsMessage};
+
+use crate::client::{Client, ClientError, Response, ResponseFuture};
+use crate::framing::parse_payload;
+use crate::jsonrpc::{encode_message, Batch, JsonRpc, Message, Params};
+use crate::router::AsyncRouter;
+
+/// One end of a JSON-RPC session over a WebSocket
+///
+/// Cloning a peer shares the session.
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonrpc_lite::websocket::Peer;
+/// use jsonrpc_lite::AsyncRouter;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let (socket, _) = tokio_tungstenite::connect_async("ws://localhost:8080").await?;
+/// let (peer, connection) = Peer::new(socket, AsyncRouter::new());
+/// tokio::spawn(connection);
+///
+/// let result = peer.request_with_params("add", vec![1.into(), 2.into()]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Peer {
+    client: Arc<Client>,
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+impl Peer {
+    /// Starts a session over a WebSocket
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The WebSocket, as a stream and sink of frames
+    /// * `router` - The router dispatching the requests and notifications of the other peer
+    ///
+    /// # Returns
+    ///
+    /// The peer, and the connection future driving the socket
+    pub fn new<T>(socket: T, router: AsyncRouter) -> (Peer, Connection)
+    where
+        T: Stream<Item = Result<WsMessage, WsError>>
+            + Sink<WsMessage, Error = WsError>
+            + Send
+            + 'static,
+    {
+        let client = Arc::new(Client::new());
+        let (sender, receiver) = mpsc::unbounded();
+        let peer = Peer {
+            client: client.clone(),
+            sender: sender.clone(),
+        };
+        let future = run(socket, router, client, sender, receiver).boxed();
+        (peer, Connection { future })
+    }
+
+    /// Calls a method of the other peer without params
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    ///
+    /// # Returns
+    ///
+    /// The `result` of the response, `ClientError::Canceled` if the connection closes first,
+    /// or `ClientError::IdInUse` if the generated id is still pending
+    pub async fn request(&self, method: &str) -> Response {
+        let (request, response) = self.client.request(method)?;
+        self.send_request(request, response).await
+    }
+
+    /// Calls a method of the other peer with params
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to call
+    /// * `params` - The parameters to pass to the method
+    ///
+    /// # Returns
+    ///
+    /// The `result` of the response, `ClientError::Canceled` if the connection closes first,
+    /// or `ClientError::IdInUse` if the generated id is still pending
+    pub async fn request_with_params<P: Into<Params>>(&self, method: &str, params: P) -> Response {
+        let (request, response) = self.client.request_with_params(method, params)?;
+        self.send_request(request, response).await
+    }
+
+    async fn send_request(&self, request: JsonRpc, response: ResponseFuture) -> Response {
+        if self
+            .sender
+            .unbounded_send(Message::Single(request))
+            .is_err()
+        {
+            self.client.cancel(response.id());
+        }
+        response.await
+    }
+
+    /// Sends a notification without params to the other peer
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to notify
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once queued, or `ClientError::Canceled` if the connection is closed
+    pub fn notify(&self, method: &str) -> Result<(), ClientError> {
+        self.send(JsonRpc::notification(method))
+    }
+
+    /// Sends a notification with params to the other peer
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method to notify
+    /// * `params` - The parameters to pass to the method
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once queued, or `ClientError::Canceled` if the connection is closed
+    pub fn notify_with_params<P: Into<Params>>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<(), ClientError> {
+        self.send(JsonRpc::notification_with_params(method, params))
+    }
+
+    fn send(&self, message: JsonRpc) -> Result<(), ClientError> {
+        self.sender
+            .unbounded_send(Message::Single(message))
+            .map_err(|_| ClientError::Canceled)
+    }
+
+    /// Closes the session, sending a close frame to the other peer
+    ///
+    /// Messages queued before are sent first. The requests still awaiting a
+    /// response resolve to `ClientError::Canceled`.
+    pub fn close(&self) {
+        self.sender.close_channel();
+    }
+
+    /// Gets the client tracking the requests awaiting a response
+    ///
+    /// # Returns
+    ///
+    /// The client of this peer
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl fmt::Debug for Peer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Peer")
+            .field("client", &self.client)
+            .field("closed", &self.sender.is_closed())
+            .finish()
+    }
+}
+
+/// Drives a WebSocket session until either side closes it
+///
+/// Resolves once the socket is closed, or with the error that ended it. The
+/// requests still awaiting a response then resolve to `ClientError::Canceled`.
+pub struct Connection {
+    future: BoxFuture<'static, Result<(), WsError>>,
+}
+
+impl Future for Connection {
+    type Output = Result<(), WsError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Connection").finish_non_exhaustive()
+    }
+}
+
+/// Forwards outgoing messages to the socket and handles incoming frames concurrently
+async fn run<T>(
+    socket: T,
+    router: AsyncRouter,
+    client: Arc<Client>,
+    sender: mpsc::UnboundedSender<Message>,
+    receiver: mpsc::UnboundedReceiver<Message>,
+) -> Result<(), WsError>
+where
+    T: Stream<Item = Result<WsMessage, WsError>>
+        + Sink<WsMessage, Error = WsError>
+        + Send
+        + 'static,
+{
+    let result = {
+        let (sink, stream) = socket.split();
+        let outgoing = receiver
+            .map(|message| Ok(WsMessage::text(encode_message(&message))))
+            .forward(sink);
+
+        let (router, client, sender) = (&router, &client, &sender);
+        let incoming = stream.try_for_each_concurrent(None, |frame| async move {
+            if let Some(response) = receive(router, client, frame).await {
+                let _ = sender.unbounded_send(response);
+            }
+            Ok(())
+        });
+
+        match future::select(Box::pin(incoming), Box::pin(outgoing)).await {
+            Either::Left((result, _)) | Either::Right((result, _)) => result,
+        }
+    };
+
+    // The outgoing queue is closed now, so no request can be sent after this
+    client.cancel_all();
+    result
+}
+
+/// Handles an incoming frame, returning the response to send back, if any
+///
+/// The responses of a batch resolve the pending requests of the client, and
+/// its requests and notifications are dispatched to the router.
+async fn receive(router: &AsyncRouter, client: &Client, frame: WsMessage) -> Option<Message> {
+    let payload = match frame {
+        WsMessage::Text(ref text) => text.as_bytes(),
+        WsMessage::Binary(ref data) => data,
+        _ => return None,
+    };
+    // Responses nobody waits for have no one to be reported to
+    match parse_payload(payload) {
+        Ok(Message::Single(message)) if is_response(&message) => {
+            let _ = client.handle_response(message);
+            None
+        }
+        Ok(Message::Batch(batch)) => {
+            let (responses, calls) = split_batch(batch);
+            if !responses.is_empty() {
+                let _ = client.handle_message(Message::Batch(responses));
+            }
+            if calls.is_empty() {
+                None
+            } else {
+                router.handle_message(Message::Batch(calls)).await
+            }
+        }
+        Ok(message) => router.handle_message(message).await,
+        Err(err) => Some(Message::Single(err.into_response())),
+    }
+}
+
+/// Checks whether a message is a response rather than a request or notification
+fn is_response(message: &JsonRpc) -> bool {
+    matches!(message, JsonRpc::Success(_) | JsonRpc::Error(_))
+}
+
+/// Splits a batch into its responses and its requests and notifications
+///
+/// Malformed entries are answered by the router when the batch holds any
+/// request or notification, and are handed to the client otherwise.
+fn split_batch(batch: Batch) -> (Batch, Batch) {
+    let has_calls = batch.messages().any(|message| !is_response(message));
+    let (responses, calls): (Vec<_>, Vec<_>) = batch.into_iter().partition(|entry| match *entry {
+        Ok(ref message) => is_response(message),
+        Err(_) => !has_calls,
+    });
+    (Batch::from_entries(responses), Batch::from_entries(calls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use serde_json::json;
+
+    #[test]
+    fn splits_mixed_batches() {
+        let mut router = AsyncRouter::new();
+        router.add_method("echo", |params: Params| async move { Ok(params) });
+        let client = Client::new();
+        let (request, response) = client.request("ping").unwrap();
+
+        let batch = Message::from(vec![
+            JsonRpc::success(request.get_id().unwrap(), &json!("pong")),
+            JsonRpc::request_with_params(7, "echo", json!([1])),
+        ]);
+        let frame = WsMessage::text(encode_message(&batch));
+        let reply = block_on(receive(&router, &client, frame));
+
+        assert_eq!(block_on(response), Ok(json!("pong")));
+        assert_eq!(
+            reply,
+            Some(Message::from(vec![JsonRpc::success(7, &json!([1]))]))
+        );
+    }
+}
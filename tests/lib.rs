@@ -1,5 +1,6 @@
-use jsonrpc_lite::JsonRpc;
-use serde_json::{json, to_value, Value};
+use jsonrpc_lite::{JsonRpc, Message, Params, Request, Success};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_string, to_value, Value};
 
 #[test]
 fn request() {
@@ -33,3 +34,116 @@ fn request_with_params() {
         })
     );
 }
+
+#[test]
+fn parse_message_round_trip() {
+    let single = r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}"#;
+    let message = JsonRpc::parse_message(single).expect("Unable to parse single message");
+    assert_eq!(
+        message,
+        Message::Single(JsonRpc::request_with_params(1, "subtract", json!([42, 23])))
+    );
+    assert_eq!(to_string(&message).unwrap(), single);
+
+    let batch = "\n\t[{\"jsonrpc\":\"2.0\",\"method\":\"notify_hello\",\"params\":[7]},\
+                 {\"jsonrpc\":\"2.0\",\"method\":\"get_data\",\"id\":\"9\"}]";
+    let message = JsonRpc::parse_message(batch).expect("Unable to parse batch message");
+    assert_eq!(
+        to_value(&message).unwrap(),
+        json!([
+            {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]},
+            {"jsonrpc": "2.0", "method": "get_data", "id": "9"}
+        ])
+    );
+
+    let response = message
+        .respond(|request| {
+            request
+                .get_id()
+                .map(|id| JsonRpc::success(id, &json!(["hello", 5])))
+        })
+        .expect("Batch response is empty");
+    assert_eq!(
+        to_value(&response).unwrap(),
+        json!([{"jsonrpc": "2.0", "result": ["hello", 5], "id": "9"}])
+    );
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Subtract {
+    minuend: i64,
+    subtrahend: i64,
+}
+
+#[test]
+fn typed_params_and_result() {
+    let input =
+        r#"{"jsonrpc":"2.0","method":"subtract","params":{"minuend":42,"subtrahend":23},"id":3}"#;
+    let request: Request<Subtract> =
+        serde_json::from_str(input).expect("Unable to parse typed request");
+    assert_eq!(
+        request,
+        Request::new(
+            3,
+            "subtract",
+            Some(Subtract {
+                minuend: 42,
+                subtrahend: 23
+            })
+        )
+    );
+    assert_eq!(to_string(&request).unwrap(), input);
+
+    let params = request.params.expect("Typed request has no params");
+    let response = Success::new(request.id, params.minuend - params.subtrahend);
+    let output = to_string(&response).unwrap();
+    assert_eq!(output, r#"{"jsonrpc":"2.0","result":19,"id":3}"#);
+
+    let response: Success<i64> = serde_json::from_str(&output).expect("Unable to parse result");
+    assert_eq!(response.result, 19);
+}
+
+#[test]
+fn typed_params_extraction() {
+    let named = Params::from(json!({"minuend": 42, "subtrahend": 23}));
+    let positional = Params::from(json!([42, 23]));
+    for params in [named, positional] {
+        let parsed: Subtract = params.parse().expect("Unable to parse params");
+        assert_eq!(
+            parsed,
+            Subtract {
+                minuend: 42,
+                subtrahend: 23
+            }
+        );
+    }
+
+    let err = Params::from(json!({"minuend": 42}))
+        .parse::<Subtract>()
+        .expect_err("Missing param was accepted");
+    assert_eq!(err.code, -32602);
+    #[cfg(feature = "param-paths")]
+    assert_eq!(
+        err.data,
+        Some(json!({"path": ".", "message": "missing field `subtrahend`"}))
+    );
+    #[cfg(not(feature = "param-paths"))]
+    assert_eq!(
+        err.data,
+        Some(json!({"message": "missing field `subtrahend`"}))
+    );
+
+    #[cfg(feature = "param-paths")]
+    {
+        let err = Params::from(json!([1, true]))
+            .parse::<(i64, i64)>()
+            .expect_err("Invalid positional param was accepted");
+        assert_eq!(
+            err.data.expect("Invalid params error has no data")["path"],
+            "[1]"
+        );
+    }
+
+    let none: Option<Subtract> = Params::None(()).parse().expect("Unable to parse no params");
+    assert_eq!(none, None);
+}
@@ -0,0 +1,149 @@
+use futures::{SinkExt, StreamExt};
+use jsonrpc_lite::framing::{FrameError, HeaderCodec, LengthPrefixedCodec, LineCodec};
+use jsonrpc_lite::{JsonRpc, Message};
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+async fn round_trip<C>(codec: C)
+where
+    C: Decoder<Item = Result<Message, jsonrpc_lite::ParseError>, Error = FrameError>
+        + Encoder<JsonRpc, Error = FrameError>
+        + Encoder<Message, Error = FrameError>
+        + Clone
+        + Send
+        + Unpin
+        + 'static,
+{
+    let (client, server) = tokio::io::duplex(16);
+    let mut sink = FramedWrite::new(client, codec.clone());
+    let mut stream = FramedRead::new(server, codec);
+
+    let request = JsonRpc::request_with_params(1, "echo", json!({"text": "a\nb"}));
+    let batch = Message::from(vec![JsonRpc::notification("a"), JsonRpc::request(2, "b")]);
+    let writer = tokio::spawn(async move {
+        sink.send(request).await.expect("Unable to send request");
+        sink.send(batch).await.expect("Unable to send batch");
+    });
+
+    let first = stream.next().await.expect("Missing request");
+    assert_eq!(
+        first
+            .expect("Unable to decode request")
+            .expect("Unable to parse request"),
+        Message::Single(JsonRpc::request_with_params(
+            1,
+            "echo",
+            json!({"text": "a\nb"})
+        ))
+    );
+    let second = stream.next().await.expect("Missing batch");
+    assert_eq!(
+        second
+            .expect("Unable to decode batch")
+            .expect("Unable to parse batch"),
+        Message::from(vec![JsonRpc::notification("a"), JsonRpc::request(2, "b")])
+    );
+    writer.await.unwrap();
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn line_codec() {
+    round_trip(LineCodec::new().decoder()).await;
+}
+
+#[tokio::test]
+async fn header_codec() {
+    round_trip(HeaderCodec::new().with_content_type("application/vscode-jsonrpc; charset=utf-8"))
+        .await;
+}
+
+#[tokio::test]
+async fn length_prefixed_codec() {
+    round_trip(LengthPrefixedCodec::new()).await;
+}
+
+#[tokio::test]
+async fn parse_errors_do_not_end_the_stream() {
+    let (mut client, server) = tokio::io::duplex(64);
+    let mut stream = FramedRead::new(server, LineCodec::new().decoder());
+    client
+        .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":3}\n\n{\"jsonrpc\":\"2.0\",\"method\":\"a\"}")
+        .await
+        .unwrap();
+    drop(client);
+
+    let err = stream
+        .next()
+        .await
+        .expect("Missing frame")
+        .expect("Unable to decode frame")
+        .expect_err("Expected a parse error");
+    assert_eq!(err.code(), -32600);
+    assert_eq!(
+        err.into_response().get_id(),
+        Some(jsonrpc_lite::Id::from(3))
+    );
+
+    let message = stream
+        .next()
+        .await
+        .expect("Missing frame")
+        .expect("Unable to decode frame");
+    assert_eq!(
+        message.unwrap(),
+        Message::Single(JsonRpc::notification("a"))
+    );
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn oversized_frames_end_the_stream() {
+    let (mut client, server) = tokio::io::duplex(64);
+    let mut stream = FramedRead::new(server, LengthPrefixedCodec::with_max_length(8));
+    client.write_all(&[0, 0, 0, 9]).await.unwrap();
+
+    match stream.next().await {
+        Some(Err(FrameError::TooLarge { limit: 8 })) => {}
+        other => panic!("Expected an oversized frame, got {:?}", other),
+    }
+    assert!(stream.next().await.is_none());
+}
+
+#[test]
+fn line_codec_decodes_split_frames() {
+    let mut codec = LineCodec::new().decoder();
+    let mut buffer = bytes::BytesMut::new();
+    let frame = b"{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n{\"jsonrpc\"";
+    for chunk in frame[..frame.len() - 12].chunks(5) {
+        buffer.extend_from_slice(chunk);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+    }
+    assert_eq!(codec.codec(), LineCodec::new());
+    buffer.extend_from_slice(&frame[frame.len() - 12..]);
+    let message = codec.decode(&mut buffer).unwrap().expect("Missing message");
+    assert_eq!(message, Ok(Message::Single(JsonRpc::notification("ping"))));
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+    buffer.extend_from_slice(b":\"2.0\",\"method\":\"pong\"}\r\n");
+    let message = codec.decode(&mut buffer).unwrap().expect("Missing message");
+    assert_eq!(message, Ok(Message::Single(JsonRpc::notification("pong"))));
+}
+
+#[test]
+fn header_codec_consumes_blank_lines() {
+    let mut codec = HeaderCodec::new();
+    let mut buffer = bytes::BytesMut::new();
+    for _ in 0..1000 {
+        buffer.extend_from_slice(b"\r\n\n");
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(buffer.is_empty());
+    }
+
+    let body = b"{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}";
+    buffer.extend_from_slice(format!("\r\nContent-Length: {}\r\n\r\n", body.len()).as_bytes());
+    buffer.extend_from_slice(body);
+    let message = codec.decode(&mut buffer).unwrap().expect("Missing message");
+    assert_eq!(message, Ok(Message::Single(JsonRpc::notification("ping"))));
+}
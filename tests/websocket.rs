@@ -0,0 +1,97 @@
+use futures::channel::mpsc;
+use futures::StreamExt;
+use jsonrpc_lite::websocket::Peer;
+use jsonrpc_lite::{AsyncRouter, ClientError, Error, Params};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+/// Starts a server peer on a loopback port and connects a client peer to it
+async fn connect(server_router: AsyncRouter, client_router: AsyncRouter) -> (Peer, Peer) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let accept = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let (peer, connection) = Peer::new(socket, server_router);
+        tokio::spawn(connection);
+        peer
+    });
+
+    let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", address))
+        .await
+        .unwrap();
+    let (client, connection) = Peer::new(socket, client_router);
+    tokio::spawn(connection);
+    (accept.await.unwrap(), client)
+}
+
+#[tokio::test]
+async fn requests_both_ways() {
+    let mut server_router = AsyncRouter::new();
+    server_router.add_method("add", |params: Params| async move {
+        let values: Vec<i64> = params.parse()?;
+        Ok(values.iter().sum::<i64>())
+    });
+
+    let (notified, mut notifications) = mpsc::unbounded();
+    let mut client_router = AsyncRouter::new();
+    client_router.add_method("name", |_: Params| async { Ok("client") });
+    client_router.add_method("progress", move |params: Params| {
+        let _ = notified.unbounded_send(Value::from(params));
+        async { Ok(()) }
+    });
+
+    let (server, client) = connect(server_router, client_router).await;
+
+    let (sum, name) = tokio::join!(
+        client.request_with_params("add", json!([1, 2, 3])),
+        server.request("name"),
+    );
+    assert_eq!(sum, Ok(json!(6)));
+    assert_eq!(name, Ok(json!("client")));
+
+    assert_eq!(
+        client.request("missing").await,
+        Err(ClientError::Rpc(Error::method_not_found()))
+    );
+    assert_eq!(
+        client
+            .request_with_params("add", json!({"a": 1}))
+            .await
+            .map_err(|err| match err {
+                ClientError::Rpc(err) => err.code,
+                err => panic!("Expected an error response, got {:?}", err),
+            }),
+        Err(-32602)
+    );
+
+    server
+        .notify_with_params("progress", json!([50]))
+        .expect("Unable to notify");
+    assert_eq!(notifications.next().await, Some(json!([50])));
+}
+
+#[tokio::test]
+async fn close_cancels_pending_requests() {
+    let mut server_router = AsyncRouter::new();
+    server_router.add_method("never", |_: Params| future_never());
+
+    let (server, client) = connect(server_router, AsyncRouter::new()).await;
+    let pending = tokio::spawn({
+        let client = client.clone();
+        async move { client.request("never").await }
+    });
+    while client.client().pending_count() == 0 {
+        tokio::task::yield_now().await;
+    }
+
+    server.close();
+    assert_eq!(pending.await.unwrap(), Err(ClientError::Canceled));
+    assert_eq!(client.request("never").await, Err(ClientError::Canceled));
+    assert_eq!(client.notify("never"), Err(ClientError::Canceled));
+}
+
+async fn future_never() -> jsonrpc_lite::Result<Value> {
+    futures::future::pending().await
+}